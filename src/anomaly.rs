@@ -0,0 +1,83 @@
+use serde::{Deserialize, Serialize};
+
+use crate::config::AnomalyConfig;
+
+// Exponentially weighted mean and variance of the borrow change between two polls
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct BorrowChangeStats {
+    pub mean: f64,
+    pub variance: f64,
+    pub samples: u32,
+}
+
+impl BorrowChangeStats {
+    pub fn std_dev(&self) -> f64 {
+        self.variance.sqrt()
+    }
+
+    // None until there are enough samples, the fixed thresholds are used instead
+    pub fn z_score(&self, change: f64, config: &AnomalyConfig) -> Option<f64> {
+        if self.samples < config.warm_up_samples {
+            return None;
+        }
+
+        let std_dev = self.std_dev().max(config.min_std_dev);
+        Some((change - self.mean) / std_dev)
+    }
+
+    pub fn update(&mut self, change: f64, config: &AnomalyConfig) {
+        if self.samples == 0 {
+            self.mean = change;
+            self.variance = 0.0;
+        } else {
+            let diff = change - self.mean;
+            let increment = config.alpha * diff;
+
+            self.mean += increment;
+            self.variance = (1.0 - config.alpha) * (self.variance + diff * increment);
+        }
+
+        self.samples = self.samples.saturating_add(1);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_borrow_change_stats() {
+        let config = AnomalyConfig {
+            alpha: 0.1,
+            warm_up_samples: 10,
+            ..Default::default()
+        };
+
+        let mut stats = BorrowChangeStats::default();
+
+        // choppy asset, borrow swings by 5% every poll
+        for i in 0..20 {
+            assert_eq!(stats.z_score(5.0, &config).is_some(), i >= 10);
+            let change = if i % 2 == 0 { 5.0 } else { -5.0 };
+            stats.update(change, &config);
+        }
+
+        assert_eq!(stats.samples, 20);
+        assert!(stats.mean.abs() < 1.0);
+
+        let usual = stats.z_score(5.0, &config).unwrap();
+        assert!(usual < 2.0, "z-score {}", usual);
+
+        let spike = stats.z_score(50.0, &config).unwrap();
+        assert!(spike > 8.0, "z-score {}", spike);
+
+        // quiet asset, the deviation is limited by the configured minimum
+        let mut stats = BorrowChangeStats::default();
+        for _ in 0..20 {
+            stats.update(0.0, &config);
+        }
+
+        assert_eq!(stats.z_score(1.0, &config), Some(1.0 / config.min_std_dev));
+        assert!(stats.z_score(10.0, &config).unwrap() > config.z_score);
+    }
+}
@@ -7,20 +7,38 @@ use rust_decimal::{Decimal, prelude::ToPrimitive};
 use serde::{Deserialize, de::DeserializeOwned};
 
 use crate::error::Result;
-use crate::structs::MarginData;
+use crate::structs::{IsolatedBorrow, IsolatedMarginData, MarginData};
 
 const BORROWINGS_URL: &str = "https://www.binance.com/bapi/margin/v1/public/margin/statistics/24h-borrow-and-repay";
+const ISOLATED_BORROWINGS_URL: &str = "https://www.binance.com/bapi/margin/v1/public/isolated-margin/statistics/24h-borrow-and-repay";
 const LEFT_AVAILABLE_URL: &str = "https://www.binance.com/bapi/margin/v1/public/margin/marketStats/available-inventory";
+const INTEREST_RATES_URL: &str = "https://www.binance.com/bapi/margin/v1/friendly/margin/vip/spec/list-all";
+const SPOT_EXCHANGE_INFO: &str = "https://api.binance.com/api/v3/exchangeInfo";
 const SPOT_DAILY_VOLUME_URL: &str = "https://api.binance.com/api/v3/ticker/24hr";
+const SPOT_DEPTH_URL: &str = "https://api.binance.com/api/v3/depth";
+// Enough levels to cover 2% around the mid price for most of the pairs
+const SPOT_DEPTH_LIMIT: &str = "500";
 
 const CANDLESTICKS_URL: &str = "https://api.binance.com/api/v3/klines";
 const CANDLES_INTERVAL: &str = "5m";
+// Keep in sync with CANDLES_WINDOW of the market streams
 const CANDLES_NUMBER: &str = "50";
+// Hourly candles are used as a volume baseline for several days back
+const BASELINE_CANDLES_INTERVAL: &str = "1h";
+// The oldest day also needs the hours before its window, which is up to 4 hours long
+pub const BASELINE_EXTRA_CANDLES: u32 = 5;
+
+const FUTURES_CANDLESTICKS_URL: &str = "https://fapi.binance.com/fapi/v1/klines";
 
 const FUTURES_EXCHANGE_INFO: &str = "https://fapi.binance.com/fapi/v1/exchangeInfo";
 const FUNDING_RATE_URL: &str = "https://fapi.binance.com/fapi/v1/premiumIndex";
+const FUNDING_HISTORY_URL: &str = "https://fapi.binance.com/fapi/v1/fundingRate";
+// Enough to cover 24 hours even for contracts with 1 hour funding interval
+const FUNDING_HISTORY_LIMIT: &str = "30";
 
 const LONG_SHORT_RATIO_URL: &str = "https://fapi.binance.com/futures/data/globalLongShortAccountRatio";
+const TOP_POSITION_RATIO_URL: &str = "https://fapi.binance.com/futures/data/topLongShortPositionRatio";
+const TOP_ACCOUNT_RATIO_URL: &str = "https://fapi.binance.com/futures/data/topLongShortAccountRatio";
 // There is no chance to use less interval, the data is returned for a 5-minute interval,
 // where both the start and end timestamps are aligned to 5-minute boundaries (e.g., 00:00, 00:05, 00:10, etc.).
 const RATIO_INTERVAL: &str = "5m";
@@ -37,7 +55,17 @@ const TO_EXCLUDE: [&str; 20] = [
     "XRP", "BNB", "ADA", "SUI", "LTC", "TRX", "PAXG", "DAI", "BFUSD",
 ];
 
-fn to_datetime_utc<'de, D>(deserializer: D) -> std::result::Result<DateTime<Utc>, D::Error>
+pub const STABLECOINS: [&str; 7] = ["USD1", "USDT", "USDC", "USDP", "FDUSD", "DAI", "BFUSD"];
+
+// Stablecoins and non-scam tokens are excluded
+pub fn exclude_majors(data: Vec<MarginData>) -> Vec<MarginData> {
+    data
+        .into_iter()
+        .filter(|item| !TO_EXCLUDE.contains(&item.asset.as_str()))
+        .collect()
+}
+
+pub fn to_datetime_utc<'de, D>(deserializer: D) -> std::result::Result<DateTime<Utc>, D::Error>
 where
     D: serde::Deserializer<'de>,
 {
@@ -64,11 +92,8 @@ impl<T> BinanceResponse<T> {
 }
 
 // {"timestamp":1753116119982,"status":404,"error":"Not Found","message":"No message available","path":"/v1/public/margin/marketStats/available-inventory/sk"}
-#[allow(unused)]
 #[derive(Debug, Deserialize)]
 pub struct BinanceError {
-    pub status: u32,
-    pub error: String,
     pub message: String,
 }
 
@@ -89,6 +114,20 @@ impl<T> MarginDataResponse<T> {
     }
 }
 
+// Specs are ordered by VIP level, the first one is for the regular users
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct InterestRateSpec {
+    daily_interest_rate: Decimal,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AssetInterestRates {
+    asset_name: String,
+    specs: Vec<InterestRateSpec>,
+}
+
 #[derive(Debug, Deserialize)]
 struct BorrowingsData {
     coins: Vec<BorrowedAsset>,
@@ -97,18 +136,32 @@ struct BorrowingsData {
 #[derive(Debug, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct BinanceOpenInterest {
-    pub symbol: String,
+    // In contract units
+    pub sum_open_interest: Decimal,
     pub sum_open_interest_value: Decimal,
     #[serde(deserialize_with = "to_datetime_utc", rename = "timestamp")]
     pub datetime: DateTime<Utc>,
 }
 
+// Only the price filter is interesting, other filters are parsed with empty tick size
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BinanceSymbolFilter {
+    pub filter_type: String,
+    pub tick_size: Option<Decimal>,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct BinanceExchangeInfoSymbol {
     pub symbol: String,
     pub contract_type: String,
     pub status: String,
+    pub base_asset: String,
+    pub quote_asset: String,
+    #[serde(deserialize_with = "to_datetime_utc")]
+    pub onboard_date: DateTime<Utc>,
+    pub filters: Vec<BinanceSymbolFilter>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -117,15 +170,40 @@ pub struct BinanceExchangeInfoResponse {
     pub symbols: Vec<BinanceExchangeInfoSymbol>,
 }
 
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BinanceSpotExchangeInfoSymbol {
+    pub symbol: String,
+    pub status: String,
+    pub base_asset: String,
+    pub quote_asset: String,
+    pub filters: Vec<BinanceSymbolFilter>,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
+pub struct BinanceSpotExchangeInfoResponse {
+    pub symbols: Vec<BinanceSpotExchangeInfoSymbol>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
 pub struct BinanceFundingRate {
-    pub symbol: String,
+    pub mark_price: Decimal,
+    pub index_price: Decimal,
     pub last_funding_rate: Decimal,
     #[serde(deserialize_with = "to_datetime_utc")]
     pub next_funding_time: DateTime<Utc>,
 }
 
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BinanceFundingRateHistory {
+    pub funding_rate: Decimal,
+    #[serde(deserialize_with = "to_datetime_utc")]
+    pub funding_time: DateTime<Utc>,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct BorrowedAsset {
@@ -136,31 +214,73 @@ struct BorrowedAsset {
     total_repay_in_usdt: Decimal,
 }
 
+impl From<BorrowedAsset> for IsolatedBorrow {
+    fn from(value: BorrowedAsset) -> Self {
+        Self {
+            total_borrow: value.total_borrow,
+            total_repay: value.total_repay,
+            total_borrow_in_usdt: value.total_borrow_in_usdt,
+            total_repay_in_usdt: value.total_repay_in_usdt,
+        }
+    }
+}
+
+// Every isolated pair has its own base and quote borrowings
+#[derive(Debug, Deserialize)]
+struct IsolatedBorrowedPair {
+    symbol: String,
+    base: BorrowedAsset,
+    quote: BorrowedAsset,
+}
+
+#[derive(Debug, Deserialize)]
+struct IsolatedBorrowingsData {
+    pairs: Vec<IsolatedBorrowedPair>,
+}
+
 #[derive(Debug, Deserialize)]
 struct AvailableInventoryData {
     assets: HashMap<String, Decimal>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct BinanceDailyVolume {
     pub symbol: String,
+    pub last_price: Decimal,
     pub volume: Decimal,
     pub quote_volume: Decimal,
 }
 
+// Price levels are [price, quantity] pairs, the best ones go first
+#[derive(Debug, Deserialize, Clone)]
+pub struct BinanceOrderBook {
+    pub bids: Vec<[Decimal; 2]>,
+    pub asks: Vec<[Decimal; 2]>,
+}
+
 type BinanceCandleResponse = Vec<Decimal>;
 
 #[derive(Debug, Deserialize, Clone)]
-pub struct BinanceCandleMarketTradeVolume {
+pub struct BinanceCandle {
     pub open_time: DateTime<Utc>,
+    pub open: Decimal,
+    pub high: Decimal,
+    pub low: Decimal,
+    pub close: Decimal,
+    // Base asset volume
+    pub volume: Decimal,
     pub close_time: DateTime<Utc>,
     pub is_closed: bool,
+    pub quote_volume: Decimal,
+    pub buy_base_volume: Decimal,
     pub sell_quote_volume: Decimal,
     pub buy_quote_volume: Decimal,
 }
 
-impl From<BinanceCandleResponse> for BinanceCandleMarketTradeVolume {
+// [open time, open, high, low, close, volume, close time, quote volume,
+// number of trades, taker buy base volume, taker buy quote volume, ignore]
+impl From<BinanceCandleResponse> for BinanceCandle {
     fn from(value: BinanceCandleResponse) -> Self {
         let open_time = value[0].to_i64().expect("Failed to parse open time");
         let close_time = value[6].to_i64().expect("Failed to parse close time");
@@ -169,7 +289,7 @@ impl From<BinanceCandleResponse> for BinanceCandleMarketTradeVolume {
         let taker_sell_quote_vol = total_quote_vol - taker_buy_quote_vol;
 
         let ts = Utc::now().timestamp_millis();
-        let is_closed = if close_time <= ts { true } else { false };
+        let is_closed = close_time <= ts;
 
         let open_time = DateTime::<Utc>::from_timestamp_millis(open_time)
             .expect("Failed to parse open time to UTC");
@@ -177,10 +297,17 @@ impl From<BinanceCandleResponse> for BinanceCandleMarketTradeVolume {
         let close_time = DateTime::<Utc>::from_timestamp_millis(close_time)
             .expect("Failed to parse close time to UTC");
 
-        BinanceCandleMarketTradeVolume {
+        BinanceCandle {
             open_time,
+            open: value[1],
+            high: value[2],
+            low: value[3],
+            close: value[4],
+            volume: value[5],
             close_time,
             is_closed,
+            quote_volume: total_quote_vol,
+            buy_base_volume: value[9],
             sell_quote_volume: taker_sell_quote_vol,
             buy_quote_volume: taker_buy_quote_vol,
         }
@@ -190,14 +317,31 @@ impl From<BinanceCandleResponse> for BinanceCandleMarketTradeVolume {
 #[derive(Debug, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct BinanceLongShortRatioPositions {
-    pub symbol: String,
-    pub long_account: Decimal,
-    pub short_account: Decimal,
     pub long_short_ratio: Decimal,
     #[serde(deserialize_with = "to_datetime_utc", rename = "timestamp")]
     pub datetime: DateTime<Utc>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LongShortRatioKind {
+    // All accounts
+    GlobalAccount,
+    // Top 20% users by margin balance, ratio of their positions
+    TopPosition,
+    // Top 20% users by margin balance, ratio of their accounts
+    TopAccount,
+}
+
+impl LongShortRatioKind {
+    fn url(&self) -> &'static str {
+        match self {
+            LongShortRatioKind::GlobalAccount => LONG_SHORT_RATIO_URL,
+            LongShortRatioKind::TopPosition => TOP_POSITION_RATIO_URL,
+            LongShortRatioKind::TopAccount => TOP_ACCOUNT_RATIO_URL,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Binance {
     client: Client,
@@ -241,6 +385,13 @@ impl Binance {
             .into_inner())
     }
 
+    async fn get_isolated_borrowings_data(&self) -> Result<IsolatedBorrowingsData> {
+        Ok(self
+            .send_request::<MarginDataResponse<IsolatedBorrowingsData>>(ISOLATED_BORROWINGS_URL)
+            .await?
+            .into_inner())
+    }
+
     async fn get_available_inventory(&self) -> Result<AvailableInventoryData> {
         Ok(self
             .send_request::<MarginDataResponse<AvailableInventoryData>>(LEFT_AVAILABLE_URL)
@@ -248,20 +399,64 @@ impl Binance {
             .into_inner())
     }
 
+    async fn get_interest_rates(&self) -> Result<Vec<AssetInterestRates>> {
+        Ok(self
+            .send_request::<MarginDataResponse<Vec<AssetInterestRates>>>(INTEREST_RATES_URL)
+            .await?
+            .into_inner())
+    }
+
+    // Hourly borrow interest rate of every asset for the regular users
+    pub async fn get_hourly_interest_rates(&self) -> Result<HashMap<String, Decimal>> {
+        let hours_in_day = Decimal::from(24);
+
+        let rates = self
+            .get_interest_rates()
+            .await?
+            .into_iter()
+            .filter_map(|asset| {
+                let spec = asset.specs.first()?;
+                Some((asset.asset_name, spec.daily_interest_rate / hours_in_day))
+            })
+            .collect();
+
+        Ok(rates)
+    }
+
     pub async fn get_futures_exchange_info(&self) -> Result<BinanceExchangeInfoResponse> {
         self.send_request(FUTURES_EXCHANGE_INFO).await
     }
 
+    pub async fn get_spot_exchange_info(&self) -> Result<BinanceSpotExchangeInfoResponse> {
+        self.send_request(SPOT_EXCHANGE_INFO).await
+    }
+
     pub async fn get_spot_daily_volume(&self, symbol: &str) -> Result<BinanceDailyVolume> {
         let query = &[("type", "MINI"), ("symbol", symbol)];
         self.send_request_with_query_params(SPOT_DAILY_VOLUME_URL, query).await
     }
 
+    // 24h tickers of all spot symbols at once
+    pub async fn get_spot_daily_volumes(&self) -> Result<Vec<BinanceDailyVolume>> {
+        let query = &[("type", "MINI")];
+        self.send_request_with_query_params(SPOT_DAILY_VOLUME_URL, query).await
+    }
+
+    pub async fn get_spot_order_book(&self, symbol: &str) -> Result<BinanceOrderBook> {
+        let query = &[("symbol", symbol), ("limit", SPOT_DEPTH_LIMIT)];
+        self.send_request_with_query_params(SPOT_DEPTH_URL, query).await
+    }
+
     pub async fn get_funding_rate(&self, symbol: &str) -> Result<BinanceFundingRate> {
         let query = &[("symbol", symbol)];
         self.send_request_with_query_params(FUNDING_RATE_URL, query).await
     }
 
+    pub async fn get_funding_rate_history(&self, symbol: &str) -> Result<Vec<BinanceFundingRateHistory>> {
+        let query = &[("symbol", symbol), ("limit", FUNDING_HISTORY_LIMIT)];
+        self.send_request_with_query_params(FUNDING_HISTORY_URL, query).await
+    }
+
     pub async fn get_open_interest(&self, symbol: &str) -> Result<Vec<BinanceOpenInterest>> {
         let query = &[
             ("symbol", symbol),
@@ -275,6 +470,12 @@ impl Binance {
         let borrowings = self.get_borrowings_data().await?;
         let available = self.get_available_inventory().await?;
 
+        // Interest rates are not essential, margin data is still useful without them
+        let interest_rates = self.get_hourly_interest_rates().await.unwrap_or_else(|e| {
+            warn!("Failed to get margin interest rates: {}", e);
+            HashMap::new()
+        });
+
         let result = borrowings
             .coins
             .into_iter()
@@ -288,6 +489,8 @@ impl Binance {
                         Decimal::ZERO
                     });
 
+                let hourly_interest_rate = interest_rates.get(&data.asset).copied();
+
                 MarginData {
                     asset: data.asset,
                     total_borrow: data.total_borrow,
@@ -295,6 +498,7 @@ impl Binance {
                     total_borrow_in_usdt: data.total_borrow_in_usdt,
                     total_repay_in_usdt: data.total_repay_in_usdt,
                     available,
+                    hourly_interest_rate,
                 }
             })
             .collect();
@@ -302,44 +506,80 @@ impl Binance {
         Ok(result)
     }
 
-    // Stablecoins and non-scam tokens are excluded
     pub async fn get_margin_data_filtered(&self) -> Result<Vec<MarginData>> {
         let response = self.get_margin_data().await?;
+        Ok(exclude_majors(response))
+    }
+
+    // Pairs with excluded base assets are skipped the same way as cross margin assets
+    pub async fn get_isolated_margin_data_filtered(&self) -> Result<Vec<IsolatedMarginData>> {
+        let response = self.get_isolated_borrowings_data().await?;
 
         let filtered = response
+            .pairs
             .into_iter()
-            .filter(|resp| !TO_EXCLUDE.contains(&resp.asset.as_str()))
+            .filter(|pair| !TO_EXCLUDE.contains(&pair.base.asset.as_str()))
+            .map(|pair| IsolatedMarginData {
+                symbol: pair.symbol,
+                base_asset: pair.base.asset.clone(),
+                quote_asset: pair.quote.asset.clone(),
+                base: pair.base.into(),
+                quote: pair.quote.into(),
+            })
             .collect();
 
         Ok(filtered)
     }
 
-    pub async fn get_candlesticks_market_volume(
+    async fn get_candles(
         &self,
+        url: &str,
         symbol: &str,
-    ) -> Result<Vec<BinanceCandleMarketTradeVolume>> {
+        interval: &str,
+        limit: &str,
+    ) -> Result<Vec<BinanceCandle>> {
         let query = &[
             ("symbol", symbol),
-            ("interval", CANDLES_INTERVAL),
-            ("limit", CANDLES_NUMBER),
+            ("interval", interval),
+            ("limit", limit),
         ];
 
         let resp = self
-            .send_request_with_query_params::<Vec<BinanceCandleResponse>>(CANDLESTICKS_URL, query)
+            .send_request_with_query_params::<Vec<BinanceCandleResponse>>(url, query)
             .await?
             .into_iter()
-            .map(BinanceCandleMarketTradeVolume::from)
+            .map(BinanceCandle::from)
             .collect();
 
         Ok(resp)
     }
 
+    pub async fn get_candlesticks_market_volume(
+        &self,
+        symbol: &str,
+    ) -> Result<Vec<BinanceCandle>> {
+        self.get_candles(CANDLESTICKS_URL, symbol, CANDLES_INTERVAL, CANDLES_NUMBER).await
+    }
+
+    pub async fn get_candlesticks_baseline(&self, symbol: &str, days: u32) -> Result<Vec<BinanceCandle>> {
+        let limit = (days * 24 + BASELINE_EXTRA_CANDLES).to_string();
+        self.get_candles(CANDLESTICKS_URL, symbol, BASELINE_CANDLES_INTERVAL, &limit).await
+    }
+
+    pub async fn get_futures_candlesticks_market_volume(
+        &self,
+        symbol: &str,
+    ) -> Result<Vec<BinanceCandle>> {
+        self.get_candles(FUTURES_CANDLESTICKS_URL, symbol, CANDLES_INTERVAL, CANDLES_NUMBER).await
+    }
+
     pub async fn get_long_short_ratio(
         &self,
         symbol: &str,
+        kind: LongShortRatioKind,
     ) -> Result<Vec<BinanceLongShortRatioPositions>> {
         let query = &[("symbol", symbol), ("period", RATIO_INTERVAL), ("limit", RATIO_LIMIT)];
-        self.send_request_with_query_params(LONG_SHORT_RATIO_URL, query).await
+        self.send_request_with_query_params(kind.url(), query).await
     }
 }
 
@@ -355,6 +595,14 @@ mod test {
         print!("Result: {:?}", result);
     }
 
+    #[ignore]
+    #[tokio::test]
+    async fn test_get_isolated_margin_data_filtered() {
+        let binance = Binance::new(Client::new());
+        let result = binance.get_isolated_margin_data_filtered().await;
+        print!("Result: {:?}", result);
+    }
+
     #[ignore]
     #[tokio::test]
     async fn test_get_available_inventory() {
@@ -363,6 +611,14 @@ mod test {
         print!("Result: {:?}", result);
     }
 
+    #[ignore]
+    #[tokio::test]
+    async fn test_get_hourly_interest_rates() {
+        let binance = Binance::new(Client::new());
+        let result = binance.get_hourly_interest_rates().await;
+        print!("Result: {:?}", result);
+    }
+
     #[ignore]
     #[tokio::test]
     async fn test_get_open_interest() {
@@ -387,6 +643,14 @@ mod test {
         print!("Result: {:?}", result);
     }
 
+    #[ignore]
+    #[tokio::test]
+    async fn test_get_funding_rate_history() {
+        let binance = Binance::new(Client::new());
+        let result = binance.get_funding_rate_history("SOLUSDT").await;
+        print!("Result: {:?}", result);
+    }
+
     #[ignore]
     #[tokio::test]
     async fn test_get_spot_daily_volume() {
@@ -395,6 +659,30 @@ mod test {
         print!("Result: {:?}", result);
     }
 
+    #[ignore]
+    #[tokio::test]
+    async fn test_get_spot_exchange_info() {
+        let binance = Binance::new(Client::new());
+        let result = binance.get_spot_exchange_info().await.map(|info| info.symbols.len());
+        print!("Result: {:?}", result);
+    }
+
+    #[ignore]
+    #[tokio::test]
+    async fn test_get_spot_daily_volumes() {
+        let binance = Binance::new(Client::new());
+        let result = binance.get_spot_daily_volumes().await.map(|volumes| volumes.len());
+        print!("Result: {:?}", result);
+    }
+
+    #[ignore]
+    #[tokio::test]
+    async fn test_get_spot_order_book() {
+        let binance = Binance::new(Client::new());
+        let result = binance.get_spot_order_book("SOLUSDT").await;
+        print!("Result: {:?}", result);
+    }
+
     #[ignore]
     #[tokio::test]
     async fn test_get_candlesticks_market_volume() {
@@ -403,11 +691,33 @@ mod test {
         print!("Result: {:?}", result);
     }
 
+    #[ignore]
+    #[tokio::test]
+    async fn test_get_candlesticks_baseline() {
+        let binance = Binance::new(Client::new());
+        let result = binance.get_candlesticks_baseline("SOLUSDT", 7).await.unwrap();
+        print!("Result: {:?}", result);
+    }
+
+    #[ignore]
+    #[tokio::test]
+    async fn test_get_futures_candlesticks_market_volume() {
+        let binance = Binance::new(Client::new());
+        let result = binance.get_futures_candlesticks_market_volume("SOLUSDT").await.unwrap();
+        print!("Result: {:?}", result);
+    }
+
     #[ignore]
     #[tokio::test]
     async fn test_get_long_short_ratio() {
         let binance = Binance::new(Client::new());
-        let result = binance.get_long_short_ratio("SOLUSDT").await;
+        let result = binance.get_long_short_ratio("SOLUSDT", LongShortRatioKind::GlobalAccount).await;
+        print!("Result: {:?}", result);
+
+        let result = binance.get_long_short_ratio("SOLUSDT", LongShortRatioKind::TopPosition).await;
+        print!("Result: {:?}", result);
+
+        let result = binance.get_long_short_ratio("SOLUSDT", LongShortRatioKind::TopAccount).await;
         print!("Result: {:?}", result);
     }
 }
@@ -0,0 +1,455 @@
+use std::cmp::Reverse;
+
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+
+use crate::binance::{
+    BinanceCandle,
+    BinanceLongShortRatioPositions,
+    BinanceOpenInterest,
+};
+use crate::error::Result;
+use crate::font::{glyph, GLYPH_HEIGHT, GLYPH_WIDTH};
+
+// 4 hours of 5-minute points
+const CHART_POINTS: usize = 48;
+const POINT_MINUTES: usize = 5;
+
+const WIDTH: u32 = 800;
+const HEIGHT: u32 = 600;
+const MARGIN: u32 = 10;
+// Glyphs are drawn 2 times bigger with 1 pixel of spacing between them
+const TEXT_SCALE: u32 = 2;
+const TEXT_HEIGHT: u32 = GLYPH_HEIGHT * TEXT_SCALE;
+const TEXT_PADDING: u32 = 4;
+
+type Rgb = [u8; 3];
+
+const BACKGROUND: Rgb = [22, 26, 34];
+const GRID: Rgb = [50, 56, 68];
+const PRICE: Rgb = [240, 185, 11];
+const BUY: Rgb = [14, 203, 129];
+const SELL: Rgb = [246, 70, 93];
+const OPEN_INTEREST: Rgb = [80, 160, 255];
+const LONG_SHORT_RATIO: Rgb = [200, 120, 255];
+const TEXT: Rgb = [200, 205, 215];
+
+// Series are ordered from the oldest to the newest point
+#[derive(Debug, Default)]
+pub struct ChartData {
+    pub price: Vec<Decimal>,
+    pub buy_volume: Vec<Decimal>,
+    pub sell_volume: Vec<Decimal>,
+    pub open_interest: Vec<Decimal>,
+    pub long_short_ratio: Vec<Decimal>,
+}
+
+// Keeps the newest CHART_POINTS items and puts them in chronological order
+fn last_points<T, K: Ord>(mut items: Vec<T>, key: impl Fn(&T) -> K) -> Vec<T> {
+    items.sort_by_key(|item| Reverse(key(item)));
+    items.truncate(CHART_POINTS);
+    items.reverse();
+    items
+}
+
+impl ChartData {
+    pub fn set_candles(&mut self, candles: &[BinanceCandle]) {
+        let candles = candles.iter().filter(|item| item.is_closed).collect();
+        let candles = last_points(candles, |item| item.open_time);
+
+        self.price = candles.iter().map(|item| item.close).collect();
+        self.buy_volume = candles.iter().map(|item| item.buy_quote_volume).collect();
+        self.sell_volume = candles.iter().map(|item| item.sell_quote_volume).collect();
+    }
+
+    pub fn set_open_interest(&mut self, open_interest: &[BinanceOpenInterest]) {
+        let open_interest = last_points(open_interest.iter().collect(), |item| item.datetime);
+        self.open_interest = open_interest.iter().map(|item| item.sum_open_interest_value).collect();
+    }
+
+    pub fn set_long_short_ratio(&mut self, ratios: &[BinanceLongShortRatioPositions]) {
+        let ratios = last_points(ratios.iter().collect(), |item| item.datetime);
+        self.long_short_ratio = ratios.iter().map(|item| item.long_short_ratio).collect();
+    }
+}
+
+struct Panel {
+    top: u32,
+    height: u32,
+}
+
+impl Panel {
+    fn bottom(&self) -> u32 {
+        self.top + self.height
+    }
+}
+
+struct Canvas {
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    fn new() -> Self {
+        let pixels = BACKGROUND
+            .iter()
+            .copied()
+            .cycle()
+            .take((WIDTH * HEIGHT * 3) as usize)
+            .collect();
+
+        Self { pixels }
+    }
+
+    fn put_pixel(&mut self, x: i64, y: i64, color: Rgb) {
+        if x < 0 || y < 0 || x >= WIDTH as i64 || y >= HEIGHT as i64 {
+            return;
+        }
+
+        let idx = ((y as u32 * WIDTH + x as u32) * 3) as usize;
+        self.pixels[idx..idx + 3].copy_from_slice(&color);
+    }
+
+    fn fill_rect(&mut self, x0: u32, y0: u32, x1: u32, y1: u32, color: Rgb) {
+        for y in y0.min(y1)..=y0.max(y1) {
+            for x in x0.min(x1)..=x0.max(x1) {
+                self.put_pixel(x.into(), y.into(), color);
+            }
+        }
+    }
+
+    // Bresenham's line, drawn 2 pixels thick
+    fn draw_line(&mut self, from: (i64, i64), to: (i64, i64), color: Rgb) {
+        let (mut x, mut y) = from;
+        let (dx, dy) = ((to.0 - x).abs(), -(to.1 - y).abs());
+        let (sx, sy) = ((to.0 - x).signum(), (to.1 - y).signum());
+        let mut err = dx + dy;
+
+        loop {
+            self.put_pixel(x, y, color);
+            self.put_pixel(x, y + 1, color);
+
+            if (x, y) == to {
+                break;
+            }
+
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y += sy;
+            }
+        }
+    }
+
+    fn text_width(text: &str) -> u32 {
+        text.chars().count() as u32 * (GLYPH_WIDTH + 1) * TEXT_SCALE
+    }
+
+    // Text on a background box, so the series lines don't cross it
+    fn draw_text(&mut self, x: u32, y: u32, text: &str, color: Rgb) {
+        let width = Self::text_width(text);
+        self.fill_rect(x.saturating_sub(2), y.saturating_sub(2), x + width, y + TEXT_HEIGHT + 1, BACKGROUND);
+
+        for (i, c) in text.chars().enumerate() {
+            let Some(rows) = glyph(c) else {
+                continue;
+            };
+
+            let left = x + i as u32 * (GLYPH_WIDTH + 1) * TEXT_SCALE;
+            for (row, bits) in rows.iter().enumerate() {
+                for column in 0..GLYPH_WIDTH {
+                    if bits & (1 << (GLYPH_WIDTH - 1 - column)) == 0 {
+                        continue;
+                    }
+
+                    let x0 = left + column * TEXT_SCALE;
+                    let y0 = y + row as u32 * TEXT_SCALE;
+                    self.fill_rect(x0, y0, x0 + TEXT_SCALE - 1, y0 + TEXT_SCALE - 1, color);
+                }
+            }
+        }
+    }
+
+    fn draw_text_right(&mut self, right: u32, y: u32, text: &str, color: Rgb) {
+        let x = right.saturating_sub(Self::text_width(text));
+        self.draw_text(x, y, text, color);
+    }
+
+    // Title in the top left corner, the highest value in the top right and the lowest in the bottom right
+    fn draw_labels(&mut self, panel: &Panel, title: &str, max: &str, min: &str) {
+        let right = WIDTH - MARGIN - TEXT_PADDING;
+        let top = panel.top + TEXT_PADDING;
+        let bottom = panel.bottom() - TEXT_PADDING - TEXT_HEIGHT;
+
+        self.draw_text(MARGIN + TEXT_PADDING, top, title, TEXT);
+        self.draw_text_right(right, top, max, TEXT);
+        self.draw_text_right(right, bottom, min, TEXT);
+    }
+
+    // Time span of the series under the last panel, the newest point is on the right
+    fn draw_time_axis(&mut self, top: u32, points: usize) {
+        let span = format_span((points.saturating_sub(1) * POINT_MINUTES) as i64);
+
+        self.draw_text(MARGIN, top, &format!("-{}", span), TEXT);
+        self.draw_text_right(WIDTH - MARGIN, top, "NOW", TEXT);
+    }
+
+    fn draw_frame(&mut self, panel: &Panel) {
+        let (left, right) = (MARGIN, WIDTH - MARGIN);
+
+        for i in 0..=4 {
+            let y = panel.top + panel.height * i / 4;
+            self.fill_rect(left, y, right, y, GRID);
+        }
+
+        self.fill_rect(left, panel.top, left, panel.bottom(), GRID);
+        self.fill_rect(right, panel.top, right, panel.bottom(), GRID);
+    }
+
+    fn draw_series(&mut self, panel: &Panel, series: &[Decimal], color: Rgb, title: &str, unit: &str) {
+        self.draw_frame(panel);
+
+        let values = to_f64(series);
+        if values.len() < 2 {
+            self.draw_labels(panel, title, "NO DATA", "");
+            return;
+        }
+
+        let (min, max) = min_max(&values);
+        let range = if max > min { max - min } else { 1.0 };
+        let step = (WIDTH - 2 * MARGIN) as f64 / (values.len() - 1) as f64;
+
+        let points = values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| {
+                let x = MARGIN as f64 + step * i as f64;
+                let y = panel.bottom() as f64 - (value - min) / range * panel.height as f64;
+                (x.round() as i64, y.round() as i64)
+            })
+            .collect::<Vec<_>>();
+
+        for pair in points.windows(2) {
+            self.draw_line(pair[0], pair[1], color);
+        }
+
+        self.draw_labels(panel, title, &format_value(max, unit), &format_value(min, unit));
+    }
+
+    // Buy volume grows up and sell volume grows down from the middle of the panel
+    fn draw_volumes(&mut self, panel: &Panel, buy: &[Decimal], sell: &[Decimal]) {
+        self.draw_frame(panel);
+
+        let title = "BUY/SELL VOLUME";
+        let (buy, sell) = (to_f64(buy), to_f64(sell));
+        if buy.is_empty() {
+            self.draw_labels(panel, title, "NO DATA", "");
+            return;
+        }
+
+        let max = buy.iter().chain(sell.iter()).copied().fold(0.0, f64::max);
+        if max <= 0.0 {
+            self.draw_labels(panel, title, "NO DATA", "");
+            return;
+        }
+
+        let middle = panel.top + panel.height / 2;
+        let half = (panel.height / 2) as f64;
+        let bar_width = (WIDTH - 2 * MARGIN) / buy.len() as u32;
+
+        for (i, (buy, sell)) in buy.iter().zip(sell.iter()).enumerate() {
+            let x0 = MARGIN + bar_width * i as u32 + 1;
+            let x1 = x0 + bar_width.saturating_sub(3);
+
+            let buy_height = (buy / max * half).round() as u32;
+            let sell_height = (sell / max * half).round() as u32;
+
+            self.fill_rect(x0, middle - buy_height, x1, middle, BUY);
+            self.fill_rect(x0, middle, x1, middle + sell_height, SELL);
+        }
+
+        // Buy and sell bars share the scale, the top is the max buy and the bottom is the max sell
+        let max = format_value(max, "$");
+        self.draw_labels(panel, title, &format!("BUY {}", max), &format!("SELL {}", max));
+    }
+
+    fn encode(self) -> Result<Vec<u8>> {
+        let mut buf = Vec::new();
+
+        {
+            let mut encoder = png::Encoder::new(&mut buf, WIDTH, HEIGHT);
+            encoder.set_color(png::ColorType::Rgb);
+            encoder.set_depth(png::BitDepth::Eight);
+
+            let mut writer = encoder.write_header()?;
+            writer.write_image_data(&self.pixels)?;
+            writer.finish()?;
+        }
+
+        Ok(buf)
+    }
+}
+
+fn to_f64(series: &[Decimal]) -> Vec<f64> {
+    series.iter().filter_map(|item| item.to_f64()).collect()
+}
+
+// Compact value for the labels, e.g. $1.25M or 0.0000123
+fn format_value(value: f64, unit: &str) -> String {
+    let abs = value.abs();
+
+    let (scaled, suffix) = match abs {
+        abs if abs >= 1e9 => (value / 1e9, "B"),
+        abs if abs >= 1e6 => (value / 1e6, "M"),
+        abs if abs >= 1e3 => (value / 1e3, "K"),
+        _ => (value, ""),
+    };
+
+    // Small values keep 3 significant digits
+    let decimals = if abs > 0.0 && abs < 1.0 { (2.0 - abs.log10().floor()) as usize } else { 2 };
+    format!("{}{:.*}{}", unit, decimals, scaled, suffix)
+}
+
+fn format_span(minutes: i64) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, minutes) => format!("{}M", minutes),
+        (hours, 0) => format!("{}H", hours),
+        (hours, minutes) => format!("{}H{}M", hours, minutes),
+    }
+}
+
+fn min_max(values: &[f64]) -> (f64, f64) {
+    values
+        .iter()
+        .fold((f64::MAX, f64::MIN), |(min, max), val| (min.min(*val), max.max(*val)))
+}
+
+// Renders price, buy/sell volumes, open interest and long short ratio panels
+// one under another. Returns None if there is no price data to draw.
+pub fn render_chart(data: &ChartData) -> Result<Option<Vec<u8>>> {
+    if data.price.len() < 2 {
+        return Ok(None);
+    }
+
+    let price = Panel { top: MARGIN, height: 200 };
+    let volume = Panel { top: price.bottom() + MARGIN, height: 130 };
+    let open_interest = Panel { top: volume.bottom() + MARGIN, height: 95 };
+    let long_short_ratio = Panel { top: open_interest.bottom() + MARGIN, height: 90 };
+
+    let mut canvas = Canvas::new();
+    canvas.draw_series(&price, &data.price, PRICE, "PRICE", "$");
+    canvas.draw_volumes(&volume, &data.buy_volume, &data.sell_volume);
+    canvas.draw_series(&open_interest, &data.open_interest, OPEN_INTEREST, "OPEN INTEREST", "$");
+    canvas.draw_series(&long_short_ratio, &data.long_short_ratio, LONG_SHORT_RATIO, "LONG/SHORT RATIO", "");
+    canvas.draw_time_axis(long_short_ratio.bottom() + MARGIN, data.price.len());
+
+    canvas.encode().map(Some)
+}
+
+// Renders total and stablecoin borrowings of the leverage index one under another
+pub fn render_leverage_chart(total: &[Decimal], stablecoin: &[Decimal]) -> Result<Option<Vec<u8>>> {
+    if total.len() < 2 {
+        return Ok(None);
+    }
+
+    let total_panel = Panel { top: MARGIN, height: 330 };
+    let stablecoin_panel = Panel { top: total_panel.bottom() + MARGIN, height: 220 };
+
+    let mut canvas = Canvas::new();
+    canvas.draw_series(&total_panel, total, PRICE, "TOTAL BORROWINGS", "$");
+    canvas.draw_series(&stablecoin_panel, stablecoin, OPEN_INTEREST, "STABLECOIN BORROWINGS", "$");
+    canvas.draw_time_axis(stablecoin_panel.bottom() + MARGIN, total.len());
+
+    canvas.encode().map(Some)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::utils::{candles_fixture, open_interest_fixture, position_ratio_fixture};
+
+    use super::*;
+
+    #[test]
+    fn test_render_chart() {
+        let mut data = ChartData::default();
+        data.set_candles(&candles_fixture());
+        data.set_open_interest(&open_interest_fixture());
+        data.set_long_short_ratio(&position_ratio_fixture());
+
+        assert_eq!(data.price.len(), CHART_POINTS);
+        assert_eq!(data.buy_volume.len(), CHART_POINTS);
+        assert_eq!(data.open_interest.len(), CHART_POINTS);
+        assert_eq!(data.long_short_ratio.len(), CHART_POINTS);
+
+        let png = render_chart(&data).unwrap().unwrap();
+        let decoder = png::Decoder::new(png.as_slice());
+        let reader = decoder.read_info().unwrap();
+        assert_eq!(reader.info().width, WIDTH);
+        assert_eq!(reader.info().height, HEIGHT);
+
+        let result = render_chart(&ChartData::default()).unwrap();
+        assert!(result.is_none());
+    }
+
+    #[test]
+    fn test_format_value() {
+        assert_eq!(format_value(1_250_000.0, "$"), "$1.25M");
+        assert_eq!(format_value(3_400_000_000.0, "$"), "$3.40B");
+        assert_eq!(format_value(45_600.0, "$"), "$45.60K");
+        assert_eq!(format_value(178.2, "$"), "$178.20");
+        assert_eq!(format_value(0.0000123, "$"), "$0.0000123");
+        assert_eq!(format_value(0.85, ""), "0.850");
+        assert_eq!(format_value(0.0, ""), "0.00");
+
+        assert_eq!(format_span(235), "3H55M");
+        assert_eq!(format_span(240), "4H");
+        assert_eq!(format_span(25), "25M");
+    }
+
+    #[test]
+    fn test_draw_labels() {
+        let mut canvas = Canvas::new();
+        let panel = Panel { top: MARGIN, height: 100 };
+        canvas.draw_labels(&panel, "PRICE", "$1.25M", "$1.10M");
+
+        // every label is drawn in the text color
+        let is_text = |x: u32, y: u32, width: u32| {
+            (x..x + width).any(|x| {
+                (y..y + TEXT_HEIGHT).any(|y| {
+                    let idx = ((y * WIDTH + x) * 3) as usize;
+                    canvas.pixels[idx..idx + 3] == TEXT
+                })
+            })
+        };
+
+        let right = WIDTH - MARGIN - TEXT_PADDING;
+        assert!(is_text(MARGIN + TEXT_PADDING, panel.top + TEXT_PADDING, Canvas::text_width("PRICE")));
+        assert!(is_text(right - Canvas::text_width("$1.25M"), panel.top + TEXT_PADDING, Canvas::text_width("$1.25M")));
+        assert!(is_text(
+            right - Canvas::text_width("$1.10M"),
+            panel.bottom() - TEXT_PADDING - TEXT_HEIGHT,
+            Canvas::text_width("$1.10M"),
+        ));
+
+        // every character of the labels is in the font
+        let labels = ["PRICE", "BUY/SELL VOLUME", "OPEN INTEREST", "LONG/SHORT RATIO", "STABLECOIN BORROWINGS", "NO DATA"];
+        assert!(labels.iter().flat_map(|label| label.chars()).all(|c| glyph(c).is_some()));
+    }
+
+    #[test]
+    fn test_render_leverage_chart() {
+        let total = (0..288).map(|i| Decimal::from(1_000_000 + i * 100)).collect::<Vec<_>>();
+        let stablecoin = (0..288).map(|i| Decimal::from(500_000 - i * 50)).collect::<Vec<_>>();
+
+        let png = render_leverage_chart(&total, &stablecoin).unwrap().unwrap();
+        let decoder = png::Decoder::new(png.as_slice());
+        let reader = decoder.read_info().unwrap();
+        assert_eq!(reader.info().width, WIDTH);
+
+        let result = render_leverage_chart(&total[..1], &stablecoin[..1]).unwrap();
+        assert!(result.is_none());
+    }
+}
@@ -1,11 +1,27 @@
+use std::collections::HashMap;
 use std::fs;
 
+use chrono::NaiveTime;
+use log::warn;
+use rust_decimal::Decimal;
 use serde::Deserialize;
 
+use crate::binance::BASELINE_EXTRA_CANDLES;
 use crate::error::ServiceError;
 
 const CONFIG_PATH: &str = "./config.json";
 
+fn default_removal_grace_hours() -> i64 {
+    24
+}
+
+// Baseline is built from hourly klines and Binance returns at most 1000 of them per request
+const MAX_RELATIVE_VOLUME_DAYS: u32 = (1000 - BASELINE_EXTRA_CANDLES) / 24;
+
+fn default_relative_volume_days() -> u32 {
+    7
+}
+
 #[derive(Debug, Deserialize)]
 pub struct TelegramConfig {
     pub token: String,
@@ -13,13 +29,230 @@ pub struct TelegramConfig {
     pub error_channel: String,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct DigestConfig {
+    // UTC time of the daily digest
+    pub daily_at: NaiveTime,
+    pub hourly: bool,
+    // Number of assets in every leaderboard
+    pub top: usize,
+    // Assets with smaller borrowings are too noisy to be ranked
+    pub min_borrow_usdt: Decimal,
+}
+
+impl Default for DigestConfig {
+    fn default() -> Self {
+        Self {
+            daily_at: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+            hourly: false,
+            top: 5,
+            min_borrow_usdt: Decimal::from(100_000),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct InventoryConfig {
+    // Alert when available inventory falls below this percent of its recent maximum
+    pub threshold_percent: Decimal,
+    // Alert when available inventory drops by this percent between two polls
+    pub drop_percent: Decimal,
+    // Period to look for the recent maximum
+    pub window_hours: i64,
+}
+
+impl Default for InventoryConfig {
+    fn default() -> Self {
+        Self {
+            threshold_percent: Decimal::from(20),
+            drop_percent: Decimal::from(50),
+            window_hours: 24,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RepaySurgeConfig {
+    pub repay_change_percent: Decimal,
+    // How much B/R ratio has to fall compared to the previous update
+    pub br_ratio_drop_percent: Decimal,
+    pub min_repay_usdt: Decimal,
+}
+
+impl Default for RepaySurgeConfig {
+    fn default() -> Self {
+        Self {
+            repay_change_percent: Decimal::ONE_HUNDRED,
+            br_ratio_drop_percent: Decimal::from(50),
+            min_repay_usdt: Decimal::from(1_000_000),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct InterestRateConfig {
+    // How much the hourly borrow rate has to grow compared to the lowest rate of the history
+    pub jump_percent: Decimal,
+    // Period of the rate history kept in redis
+    pub history_hours: i64,
+    // Rate jumps of assets with smaller borrowings are not reported
+    pub min_borrow_usdt: Decimal,
+}
+
+impl Default for InterestRateConfig {
+    fn default() -> Self {
+        Self {
+            jump_percent: Decimal::from(50),
+            history_hours: 24,
+            min_borrow_usdt: Decimal::from(1_000_000),
+        }
+    }
+}
+
+// Borrow change is compared to the exponentially weighted statistics of the asset
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct AnomalyConfig {
+    // Weight of the latest poll in the moving mean and variance
+    pub alpha: f64,
+    // Borrow signal fires when the change is this number of standard deviations above the mean
+    pub z_score: f64,
+    // Polls before the statistics are trusted, the fixed thresholds are used until then
+    pub warm_up_samples: u32,
+    // Lower bound of the standard deviation in percent, so flat series don't blow up the z-score
+    pub min_std_dev: f64,
+    // Smaller borrowings are noise for both the z-score and the fixed 10% threshold
+    pub min_borrow_usdt: Decimal,
+}
+
+impl Default for AnomalyConfig {
+    fn default() -> Self {
+        Self {
+            alpha: 0.05,
+            z_score: 4.0,
+            warm_up_samples: 288,
+            min_std_dev: 1.0,
+            min_borrow_usdt: Decimal::from(1_000_000),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SqueezeWeights {
+    pub borrow_growth: Decimal,
+    pub br_ratio: Decimal,
+    pub funding: Decimal,
+    pub open_interest: Decimal,
+    pub long_short_ratio: Decimal,
+    pub inventory: Decimal,
+}
+
+impl Default for SqueezeWeights {
+    fn default() -> Self {
+        Self {
+            borrow_growth: Decimal::from(3),
+            br_ratio: Decimal::TWO,
+            funding: Decimal::TWO,
+            open_interest: Decimal::ONE,
+            long_short_ratio: Decimal::ONE,
+            inventory: Decimal::ONE,
+        }
+    }
+}
+
+// Alt signals during market-wide moves are tagged or held back
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct MarketMoveConfig {
+    // BTC or ETH price change over any interval, disabled if not set
+    pub threshold_percent: Option<Decimal>,
+    // Hold back signals instead of tagging them
+    pub hold_signals: bool,
+}
+
+// Extra conditions a borrow report has to meet to be sent
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ReportFilters {
+    pub min_squeeze_score: Option<Decimal>,
+    // At least one interval has to reach this relative spot volume
+    pub min_relative_volume: Option<Decimal>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum FuturesAliasValue {
+    Symbol(String),
+    Contract { symbol: String, multiplier: Decimal },
+}
+
+// Perpetual contract of a renamed margin asset. Multiplier is the number of asset units
+// in one contract unit. It's 1 unless configured, as the symbol may be an asset itself, e.g. 1000SATSUSDT.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(from = "FuturesAliasValue")]
+pub struct FuturesAlias {
+    pub symbol: String,
+    pub multiplier: Decimal,
+}
+
+impl From<FuturesAliasValue> for FuturesAlias {
+    fn from(value: FuturesAliasValue) -> Self {
+        match value {
+            FuturesAliasValue::Symbol(symbol) => Self { symbol, multiplier: Decimal::ONE },
+            FuturesAliasValue::Contract { symbol, multiplier } => Self { symbol, multiplier },
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ServiceConfig {
     pub telegram: TelegramConfig,
     pub redis_url: String,
+    #[serde(default)]
+    pub digest: DigestConfig,
+    #[serde(default)]
+    pub inventory: InventoryConfig,
+    #[serde(default)]
+    pub repay_surge: RepaySurgeConfig,
+    #[serde(default)]
+    pub interest_rate: InterestRateConfig,
+    #[serde(default)]
+    pub anomaly: AnomalyConfig,
+    // Removed assets are kept in redis for this period in case they come back
+    #[serde(default = "default_removal_grace_hours")]
+    pub removal_grace_hours: i64,
+    #[serde(default)]
+    pub squeeze_weights: SqueezeWeights,
+    #[serde(default)]
+    pub report_filters: ReportFilters,
+    #[serde(default)]
+    pub market_move: MarketMoveConfig,
+    // Number of days to average spot volume for relative volume
+    #[serde(default = "default_relative_volume_days")]
+    pub relative_volume_days: u32,
+    // Margin asset to perpetual symbol for renamed tickers, e.g. "RNDR": "RENDERUSDT" or
+    // "OLD": { "symbol": "1000NEWUSDT", "multiplier": 1000 }. Contracts like 1000PEPEUSDT are resolved without it.
+    #[serde(default)]
+    pub futures_aliases: HashMap<String, FuturesAlias>,
 }
 
 pub fn read_from_file() -> Result<ServiceConfig, ServiceError> {
     let file = fs::read(CONFIG_PATH)?;
-    serde_json::from_slice::<ServiceConfig>(&file).map_err(ServiceError::from)
+    let mut config = serde_json::from_slice::<ServiceConfig>(&file).map_err(ServiceError::from)?;
+
+    let days = config.relative_volume_days.clamp(1, MAX_RELATIVE_VOLUME_DAYS);
+    if days != config.relative_volume_days {
+        warn!(
+            "relative_volume_days must be between 1 and {}, using {}",
+            MAX_RELATIVE_VOLUME_DAYS, days
+        );
+        config.relative_volume_days = days;
+    }
+
+    Ok(config)
 }
@@ -0,0 +1,198 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use chrono::Utc;
+use log::info;
+use rust_decimal::Decimal;
+use tokio::sync::mpsc::Sender;
+
+use crate::config::DigestConfig;
+use crate::leverage::build_leverage_summary;
+use crate::margin_data::MarginDataProcessor;
+use crate::structs::{MarginData, MarginDataMessage};
+use crate::utils::{find_percentage_diff, next_digest_time};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DigestPeriod {
+    Hourly,
+    Daily,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct DigestEntry {
+    pub asset: String,
+    pub value: Decimal,
+}
+
+#[derive(Debug)]
+pub struct Digest {
+    pub period: DigestPeriod,
+    pub borrow_growth: Vec<DigestEntry>,
+    pub br_ratio: Vec<DigestEntry>,
+    pub borrow_to_available: Vec<DigestEntry>,
+}
+
+fn top_entries(mut entries: Vec<DigestEntry>, top: usize) -> Vec<DigestEntry> {
+    entries.sort_by_key(|entry| Reverse(entry.value));
+    entries.truncate(top);
+    entries
+}
+
+pub fn build_digest(
+    period: DigestPeriod,
+    previous: &HashMap<String, MarginData>,
+    current: &HashMap<String, MarginData>,
+    config: &DigestConfig,
+) -> Digest {
+    let ranked = current
+        .values()
+        .filter(|item| item.total_borrow_in_usdt >= config.min_borrow_usdt)
+        .collect::<Vec<_>>();
+
+    let borrow_growth = ranked
+        .iter()
+        .filter_map(|item| {
+            let old = previous.get(&item.asset)?;
+            let value = find_percentage_diff(item.total_borrow, old.total_borrow);
+
+            Some(DigestEntry {
+                asset: item.asset.clone(),
+                value,
+            })
+        })
+        .collect();
+
+    let br_ratio = ranked
+        .iter()
+        .filter_map(|item| {
+            let value = item.total_borrow.checked_div(item.total_repay)?;
+
+            Some(DigestEntry {
+                asset: item.asset.clone(),
+                value: value.trunc_with_scale(2).normalize(),
+            })
+        })
+        .collect();
+
+    let borrow_to_available = ranked
+        .iter()
+        .filter_map(|item| {
+            let value = item.total_borrow.checked_div(item.available)? * Decimal::ONE_HUNDRED;
+
+            Some(DigestEntry {
+                asset: item.asset.clone(),
+                value: value.trunc_with_scale(2).normalize(),
+            })
+        })
+        .collect();
+
+    Digest {
+        period,
+        borrow_growth: top_entries(borrow_growth, config.top),
+        br_ratio: top_entries(br_ratio, config.top),
+        borrow_to_available: top_entries(borrow_to_available, config.top),
+    }
+}
+
+pub async fn periodic_digest(
+    processor: Arc<MarginDataProcessor>,
+    report_tx: Sender<MarginDataMessage>,
+    config: DigestConfig,
+) {
+    // Growth is measured against the data seen by the previous digest of the same period
+    let initial = processor.snapshot().await;
+    let mut snapshots = HashMap::from([
+        (DigestPeriod::Hourly, initial.clone()),
+        (DigestPeriod::Daily, initial),
+    ]);
+
+    loop {
+        let now = Utc::now();
+        let (next, period) = next_digest_time(now, config.daily_at, config.hourly);
+        let delay = (next - now).to_std().unwrap_or_default();
+
+        tokio::time::sleep(delay).await;
+        info!("Building {:?} digest", period);
+
+        let current = processor.snapshot().await;
+        let digest = build_digest(period, &snapshots[&period], &current, &config);
+
+        if period == DigestPeriod::Daily {
+            snapshots.insert(DigestPeriod::Hourly, current.clone());
+        }
+        snapshots.insert(period, current);
+
+        report_tx.send(MarginDataMessage::Digest(digest)).await.unwrap();
+
+        if period == DigestPeriod::Daily
+            && let Some(summary) = build_leverage_summary(&processor.leverage_history().await, Utc::now())
+        {
+            report_tx.send(MarginDataMessage::LeverageSummary(summary)).await.unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn margin_data(asset: &str, borrow: i64, repay: i64, available: i64) -> MarginData {
+        MarginData {
+            asset: asset.to_string(),
+            total_borrow: Decimal::from(borrow),
+            total_repay: Decimal::from(repay),
+            total_borrow_in_usdt: Decimal::from(borrow * 1000),
+            total_repay_in_usdt: Decimal::from(repay * 1000),
+            available: Decimal::from(available),
+            hourly_interest_rate: None,
+        }
+    }
+
+    fn to_map(data: Vec<MarginData>) -> HashMap<String, MarginData> {
+        data.into_iter().map(|item| (item.asset.clone(), item)).collect()
+    }
+
+    #[test]
+    fn test_build_digest() {
+        let config = DigestConfig {
+            top: 2,
+            ..Default::default()
+        };
+
+        let previous = to_map(vec![
+            margin_data("AAA", 100, 100, 1000),
+            margin_data("BBB", 200, 100, 1000),
+            margin_data("CCC", 500, 100, 1000),
+        ]);
+
+        let current = to_map(vec![
+            margin_data("AAA", 300, 100, 1000),
+            margin_data("BBB", 250, 50, 500),
+            margin_data("CCC", 500, 0, 0),
+            margin_data("DDD", 1000, 100, 100),
+            // below min_borrow_usdt
+            margin_data("EEE", 10, 1, 1),
+        ]);
+
+        let result = build_digest(DigestPeriod::Daily, &previous, &current, &config);
+
+        let expected = vec![
+            DigestEntry { asset: "AAA".to_string(), value: Decimal::from(200) },
+            DigestEntry { asset: "BBB".to_string(), value: Decimal::from(25) },
+        ];
+        assert_eq!(result.borrow_growth, expected);
+
+        let expected = vec![
+            DigestEntry { asset: "DDD".to_string(), value: Decimal::TEN },
+            DigestEntry { asset: "BBB".to_string(), value: Decimal::from(5) },
+        ];
+        assert_eq!(result.br_ratio, expected);
+
+        let expected = vec![
+            DigestEntry { asset: "DDD".to_string(), value: Decimal::ONE_THOUSAND },
+            DigestEntry { asset: "BBB".to_string(), value: Decimal::from(50) },
+        ];
+        assert_eq!(result.borrow_to_available, expected);
+    }
+}
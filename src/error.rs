@@ -22,9 +22,7 @@ impl<E: Error> From<E> for ServiceError {
 
 impl Display for ServiceError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let val = match &self {
-            ServiceError::Internal(msg) => msg,
-        };
+        let ServiceError::Internal(val) = &self;
 
         write!(f, "{}", val)
     }
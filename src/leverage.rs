@@ -0,0 +1,166 @@
+use std::cmp::Reverse;
+
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::binance::STABLECOINS;
+use crate::structs::MarginData;
+use crate::utils::find_percentage_diff;
+
+// A week of 5-minute polls
+pub const HISTORY_LEN: isize = 7 * 24 * 12;
+pub const TOP_CONTRIBUTORS: usize = 5;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LeverageContributor {
+    pub asset: String,
+    pub borrow_usdt: Decimal,
+    // Percent of the total borrowings
+    pub share: Decimal,
+}
+
+// Borrowings of all cross margin assets at one poll, majors and stablecoins included
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LeverageIndex {
+    pub time: DateTime<Utc>,
+    pub total_borrow_usdt: Decimal,
+    pub stablecoin_borrow_usdt: Decimal,
+    pub top: Vec<LeverageContributor>,
+}
+
+#[derive(Debug)]
+pub struct LeverageSummary {
+    pub latest: LeverageIndex,
+    // Compared to the oldest index within the last day
+    pub total_change: Option<Decimal>,
+    pub stablecoin_change: Option<Decimal>,
+    // Series of the last day from the oldest to the newest point
+    pub total_history: Vec<Decimal>,
+    pub stablecoin_history: Vec<Decimal>,
+}
+
+pub fn calculate_leverage_index(data: &[MarginData], now: DateTime<Utc>) -> LeverageIndex {
+    let total_borrow_usdt = data.iter().map(|item| item.total_borrow_in_usdt).sum::<Decimal>();
+
+    let stablecoin_borrow_usdt = data
+        .iter()
+        .filter(|item| STABLECOINS.contains(&item.asset.as_str()))
+        .map(|item| item.total_borrow_in_usdt)
+        .sum::<Decimal>();
+
+    let mut sorted = data.iter().collect::<Vec<_>>();
+    sorted.sort_by_key(|item| Reverse(item.total_borrow_in_usdt));
+
+    let top = sorted
+        .into_iter()
+        .take(TOP_CONTRIBUTORS)
+        .filter_map(|item| {
+            let share = item.total_borrow_in_usdt.checked_div(total_borrow_usdt)? * Decimal::ONE_HUNDRED;
+
+            Some(LeverageContributor {
+                asset: item.asset.clone(),
+                borrow_usdt: item.total_borrow_in_usdt.trunc_with_scale(2).normalize(),
+                share: share.trunc_with_scale(2).normalize(),
+            })
+        })
+        .collect();
+
+    LeverageIndex {
+        time: now,
+        total_borrow_usdt: total_borrow_usdt.trunc_with_scale(2).normalize(),
+        stablecoin_borrow_usdt: stablecoin_borrow_usdt.trunc_with_scale(2).normalize(),
+        top,
+    }
+}
+
+// History goes from the newest to the oldest index
+pub fn build_leverage_summary(history: &[LeverageIndex], now: DateTime<Utc>) -> Option<LeverageSummary> {
+    let latest = history.first()?.clone();
+
+    let mut day = history
+        .iter()
+        .filter(|item| item.time >= now - Duration::days(1))
+        .collect::<Vec<_>>();
+    day.reverse();
+
+    let oldest = day.first().filter(|oldest| oldest.time < latest.time);
+    let change = |old: Decimal, new: Decimal| (!old.is_zero()).then(|| find_percentage_diff(new, old));
+
+    Some(LeverageSummary {
+        total_change: oldest.and_then(|oldest| change(oldest.total_borrow_usdt, latest.total_borrow_usdt)),
+        stablecoin_change: oldest
+            .and_then(|oldest| change(oldest.stablecoin_borrow_usdt, latest.stablecoin_borrow_usdt)),
+        total_history: day.iter().map(|item| item.total_borrow_usdt).collect(),
+        stablecoin_history: day.iter().map(|item| item.stablecoin_borrow_usdt).collect(),
+        latest,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn margin_data(asset: &str, borrow_usdt: i64) -> MarginData {
+        MarginData {
+            asset: asset.to_string(),
+            total_borrow_in_usdt: Decimal::from(borrow_usdt),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_calculate_leverage_index() {
+        let data = vec![
+            margin_data("USDT", 500),
+            margin_data("BTC", 300),
+            margin_data("USDC", 100),
+            margin_data("PEPE", 50),
+            margin_data("WIF", 30),
+            margin_data("SOL", 20),
+        ];
+
+        let result = calculate_leverage_index(&data, Utc::now());
+        assert_eq!(result.total_borrow_usdt, Decimal::from(1000));
+        assert_eq!(result.stablecoin_borrow_usdt, Decimal::from(600));
+
+        let top = result.top.iter().map(|item| (item.asset.as_str(), item.share)).collect::<Vec<_>>();
+        let expected = vec![
+            ("USDT", Decimal::from(50)),
+            ("BTC", Decimal::from(30)),
+            ("USDC", Decimal::from(10)),
+            ("PEPE", Decimal::from(5)),
+            ("WIF", Decimal::from(3)),
+        ];
+        assert_eq!(top, expected);
+
+        let result = calculate_leverage_index(&[], Utc::now());
+        assert_eq!(result.total_borrow_usdt, Decimal::ZERO);
+        assert!(result.top.is_empty());
+    }
+
+    #[test]
+    fn test_build_leverage_summary() {
+        let now = Utc::now();
+        let index = |hours_ago: i64, total: i64, stablecoin: i64| LeverageIndex {
+            time: now - Duration::hours(hours_ago),
+            total_borrow_usdt: Decimal::from(total),
+            stablecoin_borrow_usdt: Decimal::from(stablecoin),
+            top: Vec::new(),
+        };
+
+        let history = vec![index(0, 1200, 600), index(12, 1100, 500), index(23, 1000, 400), index(30, 500, 100)];
+        let result = build_leverage_summary(&history, now).unwrap();
+
+        assert_eq!(result.latest, history[0]);
+        assert_eq!(result.total_change, Some(Decimal::from(20)));
+        assert_eq!(result.stablecoin_change, Some(Decimal::from(50)));
+        assert_eq!(result.total_history, vec![Decimal::from(1000), Decimal::from(1100), Decimal::from(1200)]);
+
+        // a single index has nothing to compare with
+        let result = build_leverage_summary(&history[..1], now).unwrap();
+        assert_eq!(result.total_change, None);
+
+        assert!(build_leverage_summary(&[], now).is_none());
+    }
+}
@@ -6,24 +6,35 @@ use reqwest::Client;
 use tokio::{task, try_join};
 
 use crate::binance::Binance;
-use crate::margin_data::margin_data_processor;
+use crate::digest::periodic_digest;
+use crate::margin_data::{margin_data_processor, MarginDataProcessor};
 use crate::config::read_from_file;
 use crate::redis::Redis;
-use crate::report::{periodic_futures_pairs_update, ReportCollector};
-use crate::report_processor::{process_new_reports, ReportProcessor};
+use crate::report::ReportCollector;
+use crate::report_processor::{process_new_reports, ReportProcessor, SignalRules};
 use crate::structs::MarginDataMessage;
+use crate::stream::{run_market_streams, MarketStream};
+use crate::symbols::{periodic_symbols_update, SymbolRegistry};
 use crate::telegram::Telegram;
 use crate::utils::calculate_delay_secs;
 
+mod anomaly;
 mod binance;
+mod chart;
 mod config;
+mod digest;
 mod error;
+mod font;
 mod structs;
 mod redis;
+mod leverage;
 mod margin_data;
 mod telegram;
 mod report;
 mod report_processor;
+mod squeeze;
+mod stream;
+mod symbols;
 mod utils;
 
 #[tokio::main]
@@ -47,19 +58,64 @@ async fn main() {
     let binance = Binance::new(client.clone());
     let redis = Arc::new(Redis::new(config.redis_url));
 
-    let report_collector = Arc::new(ReportCollector::new(binance.clone()));
-    let exch_info_task = task::spawn(periodic_futures_pairs_update(report_collector.clone()));
-    info!("Started task to update futures exchange info");
+    let symbols = Arc::new(SymbolRegistry::load(binance.clone(), config.futures_aliases).await);
+    info!("Initialized spot and futures symbol registry");
 
-    let margin_data_task = task::spawn(margin_data_processor(redis.clone(), binance.clone(), report_tx.clone()));
+    let market_stream = Arc::new(MarketStream::default());
+
+    let report_collector = ReportCollector::new(
+        binance.clone(),
+        symbols.clone(),
+        market_stream.clone(),
+        config.squeeze_weights,
+        config.relative_volume_days,
+    );
+    let report_collector = Arc::new(report_collector);
+
+    let margin_processor = MarginDataProcessor::load(
+        redis.clone(),
+        binance.clone(),
+        report_tx.clone(),
+        config.inventory,
+        config.interest_rate.clone(),
+        config.anomaly.clone(),
+        config.removal_grace_hours,
+    ).await;
+    let margin_processor = Arc::new(margin_processor);
+
+    let margin_data_task = task::spawn(margin_data_processor(margin_processor.clone()));
     info!("Started task to check binance updates and save them to redis");
 
+    let stream_task = task::spawn(run_market_streams(
+        market_stream,
+        symbols.clone(),
+        margin_processor.clone(),
+    ));
+    info!("Started task to receive market data streams");
+
+    let exch_info_task = task::spawn(periodic_symbols_update(
+        symbols,
+        margin_processor.clone(),
+        report_tx.clone(),
+    ));
+    info!("Started task to update spot and futures exchange info");
+
+    let digest_task = task::spawn(periodic_digest(margin_processor, report_tx.clone(), config.digest));
+    info!("Started task to send periodic digests");
+
     let tg = Telegram::new(client, config.telegram);
-    let report_processor = ReportProcessor::new(report_collector, redis, tg);
+    let rules = SignalRules {
+        repay_surge: config.repay_surge,
+        interest_rate: config.interest_rate,
+        anomaly: config.anomaly,
+        filters: config.report_filters,
+        market_move: config.market_move,
+    };
+    let report_processor = ReportProcessor::new(report_collector, redis, tg, rules);
     let report_task = task::spawn(process_new_reports(report_processor, report_rx));
     info!("Started task to process reports");
 
-    if let Err(e) = try_join!(exch_info_task, margin_data_task, report_task) {
+    if let Err(e) = try_join!(exch_info_task, margin_data_task, stream_task, digest_task, report_task) {
         report_tx.send(MarginDataMessage::Error(e.to_string())).await.unwrap();
         warn!("Something went wrong: {}", e)
     }
@@ -1,46 +1,203 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::Arc;
 use std::time::Duration;
 
-use log::{error, info};
+use chrono::{DateTime, Utc};
+use log::{error, info, warn};
+use rust_decimal::{Decimal, prelude::ToPrimitive};
 use tokio::sync::mpsc::Sender;
 use tokio::sync::Mutex;
 use tokio::time::interval;
 
-use crate::binance::Binance;
-use crate::structs::{MarginData, MarginDataUpdated};
+use crate::anomaly::BorrowChangeStats;
+use crate::binance::{exclude_majors, Binance};
+use crate::config::{AnomalyConfig, InterestRateConfig, InventoryConfig};
+use crate::leverage::{calculate_leverage_index, LeverageIndex, HISTORY_LEN};
+use crate::structs::{
+    InterestRatePoint,
+    InventoryDepletion,
+    InventoryDepletionKind,
+    IsolatedMarginData,
+    IsolatedMarginDataUpdated,
+    MarginData,
+    MarginDataUpdated,
+};
 use crate::redis::Redis;
 use crate::structs::MarginDataMessage;
+use crate::utils::find_percentage_diff;
 
 const REQUEST_INTERVAL: Duration = Duration::from_secs(300);
+// Binance delists a few assets at a time, a bigger drop in one poll is a truncated response
+const MAX_REMOVED_PERCENT: usize = 10;
+const MIN_MAX_REMOVED: usize = 5;
+
+type InventoryHistory = VecDeque<(DateTime<Utc>, Decimal)>;
+
+// Checks the latest available inventory against the previous poll and the maximum
+// within the history window. Falling below the threshold fires only once, when it is crossed.
+fn find_inventory_depletion(
+    history: &InventoryHistory,
+    previous: &MarginData,
+    latest: &MarginData,
+    config: &InventoryConfig,
+) -> Option<InventoryDepletion> {
+    let recent_max = history
+        .iter()
+        .map(|(_, available)| *available)
+        .chain([previous.available])
+        .max()
+        .unwrap_or(Decimal::ZERO);
+
+    if recent_max.is_zero() {
+        return None;
+    }
+
+    let threshold = recent_max * config.threshold_percent / Decimal::ONE_HUNDRED;
+    let drop_threshold = -config.drop_percent;
+
+    let change = find_percentage_diff(latest.available, previous.available);
+
+    let kind = if !previous.available.is_zero() && change <= drop_threshold {
+        InventoryDepletionKind::SharpDrop
+    } else if latest.available < threshold && previous.available >= threshold {
+        InventoryDepletionKind::BelowRecentMax
+    } else {
+        return None;
+    };
+
+    Some(InventoryDepletion {
+        kind,
+        data: latest.clone(),
+        previous: previous.available,
+        recent_max,
+    })
+}
+
+// Returns None if the response looks broken: it's empty or too many assets disappeared at once
+fn find_removed_assets(
+    previous: &HashMap<String, MarginData>,
+    latest: &[MarginData],
+) -> Option<Vec<MarginData>> {
+    if latest.is_empty() {
+        return None;
+    }
+
+    let latest = latest.iter().map(|item| item.asset.as_str()).collect::<HashSet<_>>();
+
+    let removed = previous
+        .values()
+        .filter(|item| !latest.contains(item.asset.as_str()))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let max_removed = (previous.len() * MAX_REMOVED_PERCENT / 100).max(MIN_MAX_REMOVED);
+    (removed.len() <= max_removed).then_some(removed)
+}
+
+#[derive(Debug, Default)]
+struct IsolatedMarginDataDiff {
+    // New and updated pairs to be saved
+    changed: Vec<IsolatedMarginData>,
+    updates: Vec<IsolatedMarginDataUpdated>,
+    removed: Vec<String>,
+}
+
+fn diff_isolated_margin_data(
+    previous: &HashMap<String, IsolatedMarginData>,
+    latest: Vec<IsolatedMarginData>,
+) -> IsolatedMarginDataDiff {
+    let latest_symbols = latest.iter().map(|item| item.symbol.as_str()).collect::<HashSet<_>>();
+
+    let removed = previous
+        .keys()
+        .filter(|symbol| !latest_symbols.contains(symbol.as_str()))
+        .cloned()
+        .collect();
+
+    let mut diff = IsolatedMarginDataDiff { removed, ..Default::default() };
+
+    for item in latest {
+        match previous.get(&item.symbol) {
+            None => diff.changed.push(item),
+            Some(old) if old != &item => {
+                diff.updates.push(IsolatedMarginDataUpdated {
+                    old: old.clone(),
+                    new: item.clone(),
+                    borrow_z_score: None,
+                });
+                diff.changed.push(item);
+            }
+            Some(_) => {}
+        }
+    }
+
+    diff
+}
 
 pub struct MarginDataProcessor {
     margin_data: Mutex<HashMap<String, MarginData>>,
+    // Isolated pairs by symbol
+    isolated_margin_data: Mutex<HashMap<String, IsolatedMarginData>>,
+    inventory_history: Mutex<HashMap<String, InventoryHistory>>,
+    borrow_stats: Mutex<HashMap<String, BorrowChangeStats>>,
+    // Base borrow statistics of isolated pairs by symbol
+    isolated_borrow_stats: Mutex<HashMap<String, BorrowChangeStats>>,
+    // Assets which disappeared from binance response and the time it was noticed
+    missing_since: Mutex<HashMap<String, DateTime<Utc>>>,
     redis: Arc<Redis>,
     binance: Binance,
     report_tx: Sender<MarginDataMessage>,
+    inventory_config: InventoryConfig,
+    interest_rate_config: InterestRateConfig,
+    anomaly_config: AnomalyConfig,
+    removal_grace_period: chrono::Duration,
 }
 
 impl MarginDataProcessor {
     pub fn new(
         redis: Arc<Redis>,
         binance: Binance,
-        report_tx: Sender<MarginDataMessage>
+        report_tx: Sender<MarginDataMessage>,
+        inventory_config: InventoryConfig,
+        interest_rate_config: InterestRateConfig,
+        anomaly_config: AnomalyConfig,
+        removal_grace_hours: i64,
     ) -> Self {
         Self {
             margin_data: Mutex::new(HashMap::new()),
+            isolated_margin_data: Mutex::new(HashMap::new()),
+            inventory_history: Mutex::new(HashMap::new()),
+            borrow_stats: Mutex::new(HashMap::new()),
+            isolated_borrow_stats: Mutex::new(HashMap::new()),
+            missing_since: Mutex::new(HashMap::new()),
             redis,
             binance,
             report_tx,
+            inventory_config,
+            interest_rate_config,
+            anomaly_config,
+            removal_grace_period: chrono::Duration::hours(removal_grace_hours),
         }
     }
 
     pub async fn load(
         redis: Arc<Redis>,
         binance: Binance,
-        report_tx: Sender<MarginDataMessage>
+        report_tx: Sender<MarginDataMessage>,
+        inventory_config: InventoryConfig,
+        interest_rate_config: InterestRateConfig,
+        anomaly_config: AnomalyConfig,
+        removal_grace_hours: i64,
     ) -> Self {
-        let processor = Self::new(redis, binance, report_tx);
+        let processor = Self::new(
+            redis,
+            binance,
+            report_tx,
+            inventory_config,
+            interest_rate_config,
+            anomaly_config,
+            removal_grace_hours,
+        );
         let redis_data = processor.redis.get_all_margin_data().await.expect("Failed to get margin data from redis");
 
         let redis_data = if redis_data.is_empty() {
@@ -58,17 +215,312 @@ impl MarginDataProcessor {
             *margin_data = redis_data
         }
 
+        let missing_since = processor
+            .redis
+            .get_all_missing_since()
+            .await
+            .expect("Failed to get removed assets from redis");
+
+        *processor.missing_since.lock().await = missing_since;
+
+        // Isolated pairs are saved on the first poll without reports, so there is no need to request them here
+        let isolated_data = processor
+            .redis
+            .get_all_isolated_margin_data()
+            .await
+            .expect("Failed to get isolated margin data from redis");
+
+        {
+            let mut isolated_margin_data = processor.isolated_margin_data.lock().await;
+            *isolated_margin_data = isolated_data.into_iter().map(|item| (item.symbol.clone(), item)).collect();
+        }
+
+        let borrow_stats = processor
+            .redis
+            .get_all_borrow_stats()
+            .await
+            .expect("Failed to get borrow stats from redis");
+
+        *processor.borrow_stats.lock().await = borrow_stats;
+
+        let isolated_borrow_stats = processor
+            .redis
+            .get_all_isolated_borrow_stats()
+            .await
+            .expect("Failed to get isolated borrow stats from redis");
+
+        *processor.isolated_borrow_stats.lock().await = isolated_borrow_stats;
+
         info!("Loaded data from redis to margin data processor");
         processor
     }
+
+    pub async fn snapshot(&self) -> HashMap<String, MarginData> {
+        let lock = self.margin_data.lock().await;
+        lock.clone()
+    }
+
+    // Saves the latest available inventory to the history and checks if it is depleted
+    async fn track_inventory(
+        &self,
+        previous: Option<&MarginData>,
+        latest: &MarginData,
+    ) -> Option<InventoryDepletion> {
+        let now = Utc::now();
+        let window_start = now - chrono::Duration::hours(self.inventory_config.window_hours);
+
+        let mut lock = self.inventory_history.lock().await;
+        let history = lock.entry(latest.asset.clone()).or_default();
+
+        while history.front().is_some_and(|(time, _)| *time < window_start) {
+            history.pop_front();
+        }
+
+        let depletion = previous
+            .and_then(|previous| find_inventory_depletion(history, previous, latest, &self.inventory_config));
+
+        history.push_back((now, latest.available));
+        depletion
+    }
+
+    async fn max_available(&self, asset: &str) -> Option<Decimal> {
+        let lock = self.inventory_history.lock().await;
+        lock.get(asset)?.iter().map(|(_, available)| *available).max()
+    }
+
+    // Leverage index covers all assets, including the ones excluded from the reports
+    async fn save_leverage_index(&self, all_margin_data: &[MarginData]) {
+        let index = calculate_leverage_index(all_margin_data, Utc::now());
+
+        if let Err(e) = self.redis.push_leverage_index(&index, HISTORY_LEN).await {
+            error!("Failed to save leverage index to redis: {}", e);
+        }
+    }
+
+    pub async fn leverage_history(&self) -> Vec<LeverageIndex> {
+        self.redis.get_leverage_history().await.unwrap_or_else(|e| {
+            error!("Failed to get leverage history from redis: {}", e);
+            Vec::new()
+        })
+    }
+
+    // Scores the latest borrow change against the statistics collected before it,
+    // then adds the change to them. Unchanged polls are counted as well.
+    async fn track_borrow_change(&self, previous: &MarginData, latest: &MarginData) -> Option<f64> {
+        let change = find_percentage_diff(latest.total_borrow, previous.total_borrow).to_f64()?;
+
+        let mut lock = self.borrow_stats.lock().await;
+        let stats = lock.entry(latest.asset.clone()).or_default();
+
+        let z_score = stats.z_score(change, &self.anomaly_config);
+        stats.update(change, &self.anomaly_config);
+        z_score
+    }
+
+    async fn save_borrow_stats(&self) {
+        let stats = self.borrow_stats.lock().await.clone();
+
+        if stats.is_empty() {
+            return;
+        }
+
+        if let Err(e) = self.redis.set_borrow_stats_bulk(stats).await {
+            error!("Failed to save borrow stats to redis: {}", e);
+        }
+    }
+
+    // Same statistics as for cross margin, tracked for the base borrowings of the pairs
+    // which get their own reports. Returns z-scores of the pairs which have warmed up.
+    async fn track_isolated_borrow_changes(
+        &self,
+        previous: &HashMap<String, IsolatedMarginData>,
+        latest: &[IsolatedMarginData],
+        cross_assets: &HashSet<String>,
+    ) -> HashMap<String, f64> {
+        let mut z_scores = HashMap::new();
+
+        {
+            let mut lock = self.isolated_borrow_stats.lock().await;
+
+            for item in latest.iter().filter(|item| !cross_assets.contains(&item.base_asset)) {
+                let Some(old) = previous.get(&item.symbol) else {
+                    continue;
+                };
+
+                let Some(change) = find_percentage_diff(item.base.total_borrow, old.base.total_borrow).to_f64() else {
+                    continue;
+                };
+
+                let stats = lock.entry(item.symbol.clone()).or_default();
+                if let Some(z_score) = stats.z_score(change, &self.anomaly_config) {
+                    z_scores.insert(item.symbol.clone(), z_score);
+                }
+                stats.update(change, &self.anomaly_config);
+            }
+        }
+
+        let stats = self.isolated_borrow_stats.lock().await.clone();
+        if !stats.is_empty()
+            && let Err(e) = self.redis.set_isolated_borrow_stats_bulk(stats).await
+        {
+            error!("Failed to save isolated borrow stats to redis: {}", e);
+        }
+
+        z_scores
+    }
+
+    // One history entry per poll, so the list length is derived from the poll interval
+    async fn save_interest_rates(&self, latest: &[MarginData]) {
+        let now = Utc::now();
+        let max_len = self.interest_rate_config.history_hours * 3600 / REQUEST_INTERVAL.as_secs() as i64;
+
+        let rates = latest
+            .iter()
+            .filter_map(|item| {
+                let hourly_rate = item.hourly_interest_rate?;
+                Some((item.asset.clone(), InterestRatePoint { time: now, hourly_rate }))
+            })
+            .collect::<Vec<_>>();
+
+        if rates.is_empty() {
+            return;
+        }
+
+        if let Err(e) = self.redis.push_interest_rates(rates, max_len.max(1) as isize).await {
+            error!("Failed to save interest rates to redis: {}", e);
+        }
+    }
+
+    // Isolated pairs are diffed separately from cross margin. Only the assets which are not
+    // on cross margin get their own reports, others show isolated pairs in the cross report.
+    async fn track_isolated_margin_data(&self, cross_assets: &HashSet<String>) {
+        let latest = match self.binance.get_isolated_margin_data_filtered().await {
+            Ok(latest) => latest,
+            Err(e) => {
+                error!("Failed to get isolated margin data: {}", e);
+                return;
+            }
+        };
+
+        let previous = self.isolated_margin_data.lock().await.clone();
+        let z_scores = self.track_isolated_borrow_changes(&previous, &latest, cross_assets).await;
+        let diff = diff_isolated_margin_data(&previous, latest);
+
+        if !diff.removed.is_empty() {
+            if let Err(e) = self.redis.delete_isolated_margin_data(diff.removed.clone()).await {
+                error!("Failed to delete removed isolated pairs from redis: {}", e);
+                return;
+            }
+
+            if let Err(e) = self.redis.delete_isolated_borrow_stats(&diff.removed).await {
+                error!("Failed to delete isolated borrow stats from redis: {}", e);
+            }
+
+            let mut lock = self.isolated_margin_data.lock().await;
+            let mut stats = self.isolated_borrow_stats.lock().await;
+            for symbol in &diff.removed {
+                lock.remove(symbol);
+                stats.remove(symbol);
+            }
+        }
+
+        if diff.changed.is_empty() {
+            return;
+        }
+
+        if let Err(e) = self.redis.set_isolated_margin_data_bulk(diff.changed.clone()).await {
+            error!("Failed to save isolated margin data to redis: {}", e);
+            return;
+        }
+
+        {
+            let mut lock = self.isolated_margin_data.lock().await;
+            lock.extend(diff.changed.into_iter().map(|item| (item.symbol.clone(), item)));
+        }
+
+        for mut update in diff.updates {
+            if !cross_assets.contains(&update.new.base_asset) {
+                update.borrow_z_score = z_scores.get(&update.new.symbol).copied();
+                self.report_tx.send(MarginDataMessage::IsolatedUpdate(update)).await.unwrap();
+            }
+        }
+    }
+
+    // Removed assets are reported as soon as they disappear, but cleaned only
+    // after the grace period, so a temporary gap in binance data does not wipe them.
+    async fn track_removed_assets(&self, removed: Vec<MarginData>) {
+        let now = Utc::now();
+
+        let expired = {
+            let mut missing_since = self.missing_since.lock().await;
+
+            // Forget assets which came back before the grace period ended
+            let removed_assets = removed.iter().map(|item| item.asset.as_str()).collect::<HashSet<_>>();
+            let returned = missing_since
+                .keys()
+                .filter(|asset| !removed_assets.contains(asset.as_str()))
+                .cloned()
+                .collect::<Vec<_>>();
+
+            if !returned.is_empty() {
+                if let Err(e) = self.redis.delete_missing_since(&returned).await {
+                    error!("Failed to delete returned assets from redis: {}", e);
+                }
+
+                missing_since.retain(|asset, _| removed_assets.contains(asset.as_str()));
+            }
+
+            let mut expired = Vec::new();
+
+            for item in removed {
+                match missing_since.get(&item.asset) {
+                    None => {
+                        // Saved first, so a restart does not announce the removal again
+                        if let Err(e) = self.redis.set_missing_since(&item.asset, now).await {
+                            error!("Failed to save removal time of {} to redis: {}", item.asset, e);
+                        }
+
+                        missing_since.insert(item.asset.clone(), now);
+                        self.report_tx.send(MarginDataMessage::Removed(item)).await.unwrap();
+                    }
+                    Some(since) if now - *since >= self.removal_grace_period => {
+                        expired.push(item.asset);
+                    }
+                    Some(_) => {}
+                }
+            }
+
+            expired
+        };
+
+        for asset in expired {
+            if let Err(e) = self.redis.delete_margin_data(&asset).await {
+                error!("Failed to delete removed asset {} from redis: {}", asset, e);
+                continue;
+            }
+
+            if let Err(e) = self.redis.delete_interest_rate_history(&asset).await {
+                error!("Failed to delete interest rate history of {}: {}", asset, e);
+            }
+
+            if let Err(e) = self.redis.delete_borrow_stats(&asset).await {
+                error!("Failed to delete borrow stats of {}: {}", asset, e);
+            }
+
+            if let Err(e) = self.redis.delete_missing_since(std::slice::from_ref(&asset)).await {
+                error!("Failed to delete removal time of {}: {}", asset, e);
+            }
+
+            self.margin_data.lock().await.remove(&asset);
+            self.inventory_history.lock().await.remove(&asset);
+            self.borrow_stats.lock().await.remove(&asset);
+            self.missing_since.lock().await.remove(&asset);
+            info!("Removed {} from margin data", asset);
+        }
+    }
 }
 
-pub async fn margin_data_processor(
-    redis: Arc<Redis>,
-    binance: Binance,
-    report_tx: Sender<MarginDataMessage>
-) {
-    let processor = MarginDataProcessor::load(redis, binance, report_tx).await;
+pub async fn margin_data_processor(processor: Arc<MarginDataProcessor>) {
     info!("Starting margin data processor...");
 
     let mut interval = interval(REQUEST_INTERVAL);
@@ -76,32 +528,60 @@ pub async fn margin_data_processor(
     loop {
         interval.tick().await;
 
-        let Ok(latest_binance_resp) = processor.binance.get_margin_data_filtered().await else {
+        let Ok(all_margin_data) = processor.binance.get_margin_data().await else {
             let msg = "Error while requesting binance data. Check logs";
             processor.report_tx.send(MarginDataMessage::Error(msg.to_string())).await.unwrap();
             continue;
         };
 
-        let previous_resp_data = {
-            let lock = processor.margin_data.lock().await;
-            lock.clone()
-        };
+        processor.save_leverage_index(&all_margin_data).await;
+        let latest_binance_resp = exclude_majors(all_margin_data);
+
+        let previous_resp_data = processor.snapshot().await;
+
+        match find_removed_assets(&previous_resp_data, &latest_binance_resp) {
+            Some(removed) => processor.track_removed_assets(removed).await,
+            None => warn!("Binance margin data has {} assets, skipping removal check", latest_binance_resp.len()),
+        }
+        processor.save_interest_rates(&latest_binance_resp).await;
+
+        let cross_assets = latest_binance_resp.iter().map(|item| item.asset.clone()).collect();
+        processor.track_isolated_margin_data(&cross_assets).await;
 
         let mut next_redis_updates = Vec::new();
 
-        for latest_resp_item in latest_binance_resp {
-            match previous_resp_data.get(&latest_resp_item.asset) {
+        for mut latest_resp_item in latest_binance_resp {
+            let previous_item = previous_resp_data.get(&latest_resp_item.asset);
+
+            // Keep the known rate if binance failed to return it this time
+            if latest_resp_item.hourly_interest_rate.is_none()
+                && let Some(previous_item) = previous_item
+            {
+                latest_resp_item.hourly_interest_rate = previous_item.hourly_interest_rate;
+            }
+
+            if let Some(depletion) = processor.track_inventory(previous_item, &latest_resp_item).await {
+                processor.report_tx.send(MarginDataMessage::InventoryDepletion(depletion)).await.unwrap();
+            }
+
+            match previous_item {
                 None => {
                     next_redis_updates.push(latest_resp_item.clone());
                     processor.report_tx.send(MarginDataMessage::New(latest_resp_item)).await.unwrap();
                 },
                 Some(previous_item) => {
+                    let borrow_z_score = processor.track_borrow_change(previous_item, &latest_resp_item).await;
+
                     if previous_item != &latest_resp_item {
                         next_redis_updates.push(latest_resp_item.clone());
 
+                        let max_available = processor.max_available(&latest_resp_item.asset).await;
+
                         let updated = MarginDataUpdated {
                             old: previous_item.clone(),
-                            new: latest_resp_item
+                            new: latest_resp_item,
+                            borrow_z_score,
+                            max_available,
                         };
 
                         processor.report_tx.send(MarginDataMessage::Update(updated)).await.unwrap();
@@ -110,6 +590,8 @@ pub async fn margin_data_processor(
             }
         }
 
+        processor.save_borrow_stats().await;
+
         if !next_redis_updates.is_empty() {
             match processor.redis.set_margin_data_bulk(next_redis_updates.clone()).await {
                 Ok(_) => {
@@ -130,3 +612,126 @@ pub async fn margin_data_processor(
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::structs::IsolatedBorrow;
+
+    use super::*;
+
+    fn margin_data(available: i64) -> MarginData {
+        MarginData {
+            available: Decimal::from(available),
+            ..Default::default()
+        }
+    }
+
+    fn inventory_history(values: &[i64]) -> InventoryHistory {
+        values
+            .iter()
+            .map(|val| (Utc::now(), Decimal::from(*val)))
+            .collect()
+    }
+
+    #[test]
+    fn test_find_removed_assets() {
+        let previous = ["SOL", "PEPE", "WIF"]
+            .into_iter()
+            .map(|asset| {
+                let data = MarginData { asset: asset.to_string(), ..Default::default() };
+                (asset.to_string(), data)
+            })
+            .collect::<HashMap<_, _>>();
+
+        let latest = ["SOL", "WIF", "NEW"]
+            .into_iter()
+            .map(|asset| MarginData { asset: asset.to_string(), ..Default::default() })
+            .collect::<Vec<_>>();
+
+        let result = find_removed_assets(&previous, &latest).unwrap();
+        let result = result.into_iter().map(|item| item.asset).collect::<Vec<_>>();
+        assert_eq!(result, vec!["PEPE".to_string()]);
+
+        // empty response is not a removal of every asset
+        assert!(find_removed_assets(&previous, &[]).is_none());
+
+        // truncated response drops too many assets at once
+        let previous = (0..100)
+            .map(|i| {
+                let data = MarginData { asset: format!("A{}", i), ..Default::default() };
+                (data.asset.clone(), data)
+            })
+            .collect::<HashMap<_, _>>();
+
+        let latest = previous.values().take(95).cloned().collect::<Vec<_>>();
+        assert_eq!(find_removed_assets(&previous, &latest).unwrap().len(), 5);
+
+        let latest = previous.values().take(50).cloned().collect::<Vec<_>>();
+        assert!(find_removed_assets(&previous, &latest).is_none());
+    }
+
+    #[test]
+    fn test_find_inventory_depletion() {
+        let config = InventoryConfig::default();
+
+        // 20% of the recent max is 400
+        let history = inventory_history(&[1000, 2000, 500]);
+        let result = find_inventory_depletion(&history, &margin_data(500), &margin_data(450), &config);
+        assert!(result.is_none());
+
+        let result = find_inventory_depletion(&history, &margin_data(450), &margin_data(390), &config).unwrap();
+        assert_eq!(result.kind, InventoryDepletionKind::BelowRecentMax);
+        assert_eq!(result.recent_max, Decimal::from(2000));
+        assert_eq!(result.change_from_recent_max(), Decimal::new(-805, 1));
+
+        // already below the threshold
+        let result = find_inventory_depletion(&history, &margin_data(390), &margin_data(380), &config);
+        assert!(result.is_none());
+
+        let result = find_inventory_depletion(&history, &margin_data(1000), &margin_data(400), &config).unwrap();
+        assert_eq!(result.kind, InventoryDepletionKind::SharpDrop);
+        assert_eq!(result.change_from_previous(), Decimal::from(-60));
+
+        let history = inventory_history(&[]);
+        let result = find_inventory_depletion(&history, &margin_data(0), &margin_data(0), &config);
+        assert!(result.is_none());
+    }
+
+    #[test]
+    fn test_diff_isolated_margin_data() {
+        let pair = |base: &str, borrow: i64| {
+            let borrowed = IsolatedBorrow {
+                total_borrow: Decimal::from(borrow),
+                total_repay: Decimal::ONE,
+                total_borrow_in_usdt: Decimal::from(borrow * 10),
+                total_repay_in_usdt: Decimal::TEN,
+            };
+
+            IsolatedMarginData {
+                symbol: format!("{}USDT", base),
+                base_asset: base.to_string(),
+                quote_asset: "USDT".to_string(),
+                base: borrowed.clone(),
+                quote: borrowed,
+            }
+        };
+
+        let previous = [pair("ACE", 100), pair("XAI", 200), pair("OLD", 300)]
+            .into_iter()
+            .map(|item| (item.symbol.clone(), item))
+            .collect::<HashMap<_, _>>();
+
+        let latest = vec![pair("ACE", 100), pair("XAI", 250), pair("NEW", 50)];
+        let diff = diff_isolated_margin_data(&previous, latest);
+
+        let changed = diff.changed.iter().map(|item| item.symbol.as_str()).collect::<Vec<_>>();
+        assert_eq!(changed, vec!["XAIUSDT", "NEWUSDT"]);
+
+        assert_eq!(diff.updates.len(), 1);
+        let update = diff.updates[0].base_update();
+        assert_eq!(update.new.asset, "XAI");
+        assert_eq!(update.borrow_change(), Decimal::from(25));
+
+        assert_eq!(diff.removed, vec!["OLDUSDT".to_string()]);
+    }
+}
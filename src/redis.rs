@@ -1,11 +1,18 @@
+use std::collections::HashMap;
+
 use chrono::{DateTime, Utc};
 use log::error;
 use redis::{AsyncCommands, Client};
+use serde::de::DeserializeOwned;
 
 use crate::error::{Result, ServiceError};
-use crate::structs::MarginData;
+use crate::anomaly::BorrowChangeStats;
+use crate::leverage::LeverageIndex;
+use crate::structs::{InterestRatePoint, IsolatedMarginData, MarginData};
+
+const LEVERAGE_INDEX_KEY: &str = "leverage-index";
 
-fn deserialize_redis_data(entries: Vec<String>) -> Result<Vec<MarginData>> {
+fn deserialize_redis_data<T: DeserializeOwned>(entries: Vec<String>) -> Result<Vec<T>> {
     let mut deserialized_data = Vec::with_capacity(entries.len());
 
     for entry in entries {
@@ -35,10 +42,30 @@ impl Redis {
         format!("margin-data-{}", symbol)
     }
 
+    fn isolated_margin_data_key(&self, symbol: &str) -> String {
+        format!("isolated-margin-data-{}", symbol)
+    }
+
+    fn borrow_stats_key(&self, symbol: &str) -> String {
+        format!("borrow-stats-{}", symbol)
+    }
+
+    fn isolated_borrow_stats_key(&self, symbol: &str) -> String {
+        format!("isolated-borrow-stats-{}", symbol)
+    }
+
+    fn missing_since_key(&self, symbol: &str) -> String {
+        format!("missing-since-{}", symbol)
+    }
+
     fn last_update_key(&self, symbol: &str) -> String {
         format!("last-update-{}", symbol)
     }
 
+    fn interest_rate_history_key(&self, symbol: &str) -> String {
+        format!("interest-rate-history-{}", symbol)
+    }
+
     pub async fn set_margin_data_bulk(&self, data: Vec<MarginData>) -> Result<()> {
         let mut conn = self.client.get_multiplexed_async_connection().await?;
 
@@ -56,6 +83,13 @@ impl Redis {
         Ok(())
     }
 
+    pub async fn delete_margin_data(&self, symbol: &str) -> Result<()> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        let key = self.margin_data_key(symbol);
+        let _: () = conn.del(key).await?;
+        Ok(())
+    }
+
     pub async fn get_all_margin_data(&self) -> Result<Vec<MarginData>> {
         let mut conn = self.client.get_multiplexed_async_connection().await?;
         let keys: Vec<String> = conn.keys("margin-data-*").await?;
@@ -68,6 +102,173 @@ impl Redis {
         deserialize_redis_data(result)
     }
 
+    pub async fn set_isolated_margin_data_bulk(&self, data: Vec<IsolatedMarginData>) -> Result<()> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+
+        let data = data
+            .into_iter()
+            .map(|item| {
+                let key = self.isolated_margin_data_key(&item.symbol);
+                let val = serde_json::to_string(&item).unwrap();
+
+                (key, val)
+            })
+            .collect::<Vec<_>>();
+
+        let _: () = conn.mset(&data).await?;
+        Ok(())
+    }
+
+    pub async fn delete_isolated_margin_data(&self, symbols: Vec<String>) -> Result<()> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+
+        let keys = symbols
+            .iter()
+            .map(|symbol| self.isolated_margin_data_key(symbol))
+            .collect::<Vec<_>>();
+
+        let _: () = conn.del(keys).await?;
+        Ok(())
+    }
+
+    pub async fn get_all_isolated_margin_data(&self) -> Result<Vec<IsolatedMarginData>> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        let keys: Vec<String> = conn.keys("isolated-margin-data-*").await?;
+
+        if keys.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let result = conn.mget(keys).await?;
+        deserialize_redis_data(result)
+    }
+
+    // Isolated pairs which have the asset as a base
+    pub async fn get_isolated_margin_data(&self, base_asset: &str) -> Result<Vec<IsolatedMarginData>> {
+        let mut data = self.get_all_isolated_margin_data().await?;
+        data.retain(|item| item.base_asset == base_asset);
+        Ok(data)
+    }
+
+    async fn set_stats_bulk(
+        &self,
+        stats: HashMap<String, BorrowChangeStats>,
+        key: impl Fn(&str) -> String,
+    ) -> Result<()> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+
+        let data = stats
+            .into_iter()
+            .map(|(symbol, item)| {
+                let val = serde_json::to_string(&item).unwrap();
+                (key(&symbol), val)
+            })
+            .collect::<Vec<_>>();
+
+        let _: () = conn.mset(&data).await?;
+        Ok(())
+    }
+
+    async fn get_all_stats(&self, prefix: &str) -> Result<HashMap<String, BorrowChangeStats>> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        let keys: Vec<String> = conn.keys(format!("{}*", prefix)).await?;
+
+        if keys.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let values: Vec<String> = conn.mget(&keys).await?;
+        let stats = deserialize_redis_data(values)?;
+
+        let symbols = keys
+            .iter()
+            .map(|key| key.trim_start_matches(prefix).to_string());
+
+        Ok(symbols.zip(stats).collect())
+    }
+
+    pub async fn set_borrow_stats_bulk(&self, stats: HashMap<String, BorrowChangeStats>) -> Result<()> {
+        self.set_stats_bulk(stats, |symbol| self.borrow_stats_key(symbol)).await
+    }
+
+    pub async fn get_all_borrow_stats(&self) -> Result<HashMap<String, BorrowChangeStats>> {
+        self.get_all_stats("borrow-stats-").await
+    }
+
+    pub async fn delete_borrow_stats(&self, symbol: &str) -> Result<()> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        let key = self.borrow_stats_key(symbol);
+        let _: () = conn.del(key).await?;
+        Ok(())
+    }
+
+    // Isolated pairs are keyed by their symbol
+    pub async fn set_isolated_borrow_stats_bulk(&self, stats: HashMap<String, BorrowChangeStats>) -> Result<()> {
+        self.set_stats_bulk(stats, |symbol| self.isolated_borrow_stats_key(symbol)).await
+    }
+
+    pub async fn get_all_isolated_borrow_stats(&self) -> Result<HashMap<String, BorrowChangeStats>> {
+        self.get_all_stats("isolated-borrow-stats-").await
+    }
+
+    pub async fn delete_isolated_borrow_stats(&self, symbols: &[String]) -> Result<()> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+
+        let keys = symbols
+            .iter()
+            .map(|symbol| self.isolated_borrow_stats_key(symbol))
+            .collect::<Vec<_>>();
+
+        let _: () = conn.del(keys).await?;
+        Ok(())
+    }
+
+    pub async fn set_missing_since(&self, symbol: &str, since: DateTime<Utc>) -> Result<()> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        let key = self.missing_since_key(symbol);
+        let _: () = conn.set(key, since.to_string()).await?;
+        Ok(())
+    }
+
+    pub async fn delete_missing_since(&self, symbols: &[String]) -> Result<()> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+
+        let keys = symbols
+            .iter()
+            .map(|symbol| self.missing_since_key(symbol))
+            .collect::<Vec<_>>();
+
+        let _: () = conn.del(keys).await?;
+        Ok(())
+    }
+
+    // Assets which disappeared from binance data and the time it was noticed
+    pub async fn get_all_missing_since(&self) -> Result<HashMap<String, DateTime<Utc>>> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        let keys: Vec<String> = conn.keys("missing-since-*").await?;
+
+        if keys.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let values: Vec<String> = conn.mget(&keys).await?;
+
+        let result = keys
+            .iter()
+            .zip(values)
+            .filter_map(|(key, value)| {
+                let since = value
+                    .parse::<DateTime<Utc>>()
+                    .map_err(|e| error!("Failed to parse redis datetime: {}", e))
+                    .ok()?;
+
+                Some((key.trim_start_matches("missing-since-").to_string(), since))
+            })
+            .collect();
+
+        Ok(result)
+    }
+
     pub async fn set_last_update(&self, symbol: &str, last_update: DateTime<Utc>) -> Result<()> {
         let mut conn = self.client.get_multiplexed_async_connection().await?;
         let key = self.last_update_key(symbol);
@@ -92,4 +293,65 @@ impl Redis {
 
         Ok(result)
     }
+
+    // Newest rates are pushed to the head, lists are capped at max_len entries
+    pub async fn push_interest_rates(
+        &self,
+        rates: Vec<(String, InterestRatePoint)>,
+        max_len: isize,
+    ) -> Result<()> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        let mut pipe = redis::pipe();
+
+        for (symbol, point) in rates {
+            let key = self.interest_rate_history_key(&symbol);
+            let val = serde_json::to_string(&point).unwrap();
+
+            pipe.lpush(&key, val).ignore();
+            pipe.ltrim(&key, 0, max_len - 1).ignore();
+        }
+
+        let _: () = pipe.query_async(&mut conn).await?;
+        Ok(())
+    }
+
+    // Newest first
+    pub async fn get_interest_rate_history(&self, symbol: &str) -> Result<Vec<InterestRatePoint>> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        let key = self.interest_rate_history_key(symbol);
+        let entries: Vec<String> = conn.lrange(key, 0, -1).await?;
+
+        entries
+            .iter()
+            .map(|entry| serde_json::from_str(entry).map_err(|e| ServiceError::internal(e.to_string())))
+            .collect()
+    }
+
+    pub async fn delete_interest_rate_history(&self, symbol: &str) -> Result<()> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        let key = self.interest_rate_history_key(symbol);
+        let _: () = conn.del(key).await?;
+        Ok(())
+    }
+
+    // Newest index is pushed to the head, the list is capped at max_len entries
+    pub async fn push_leverage_index(&self, index: &LeverageIndex, max_len: isize) -> Result<()> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        let val = serde_json::to_string(index).unwrap();
+
+        let _: () = redis::pipe()
+            .lpush(LEVERAGE_INDEX_KEY, val).ignore()
+            .ltrim(LEVERAGE_INDEX_KEY, 0, max_len - 1).ignore()
+            .query_async(&mut conn)
+            .await?;
+
+        Ok(())
+    }
+
+    // Newest first
+    pub async fn get_leverage_history(&self) -> Result<Vec<LeverageIndex>> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        let entries: Vec<String> = conn.lrange(LEVERAGE_INDEX_KEY, 0, -1).await?;
+        deserialize_redis_data(entries)
+    }
 }
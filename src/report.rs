@@ -1,33 +1,87 @@
-use std::collections::HashSet;
+use std::cmp::Reverse;
 use std::fmt::Display;
 use std::sync::Arc;
-use std::time::Duration;
 
-use chrono::Utc;
-use log::{error, info};
+use chrono::{DateTime, Duration, Utc};
+use log::error;
 use rust_decimal::Decimal;
-use tokio::sync::RwLock;
-use tokio::time::interval;
 
 use crate::binance::{
     Binance,
-    BinanceCandleMarketTradeVolume,
+    BinanceCandle,
     BinanceDailyVolume,
+    BinanceFundingRate,
+    BinanceFundingRateHistory,
     BinanceLongShortRatioPositions,
     BinanceOpenInterest,
+    BinanceOrderBook,
+    LongShortRatioKind,
 };
-use crate::error::Result;
-use crate::structs::{MarginDataUpdated, TimeDifference};
+use crate::chart::ChartData;
+use crate::config::SqueezeWeights;
+use crate::squeeze::{calculate_squeeze_score, max_squeeze_score, SqueezeInputs, SqueezeScore};
+use crate::stream::{LiquidationSide, LiquidationsFeed, Market, MarketStream};
+use crate::structs::{InterestRatePoint, IsolatedMarginData, MarginData, MarginDataUpdated, SignalKind, TimeDifference};
+use crate::symbols::{FuturesContract, SpotPair, SymbolRegistry};
 use crate::utils::find_percentage_diff;
 
-const EXCHANGE_INFO_UPDATE_INTERVAL: Duration = Duration::from_secs(750);
+const FUNDING_HISTORY_SHOWN: usize = 3;
+const DEFAULT_FUNDING_INTERVAL_HOURS: i64 = 8;
+// Order book depth is measured within these percents around the mid price
+const DEPTH_BANDS: [i64; 2] = [1, 2];
 const INTERVALS: [Interval; 4] = [Interval::M5, Interval::M15, Interval::H1, Interval::H4];
+// Majors which show the direction of the whole market
+pub const MARKET_SYMBOLS: [(&str, &str); 2] = [("BTC", "BTCUSDT"), ("ETH", "ETHUSDT")];
 
 pub struct Report {
+    pub kind: SignalKind,
     pub symbol: String,
+    pub market: MarketContext,
     pub margin_data: MarginDataReport,
     pub spot: SpotReport,
     pub futures: Option<FuturesReport>,
+    pub squeeze: SqueezeScore,
+    pub chart: ChartData,
+}
+
+// First report of an asset which has just been added to margin
+pub struct NewAssetReport {
+    pub data: MarginData,
+    pub spot: SpotReport,
+    pub futures: Option<FuturesReport>,
+    pub chart: ChartData,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct MarketMove {
+    pub asset: String,
+    pub changes: Vec<PriceChange>,
+}
+
+#[derive(Debug, Default)]
+pub struct MarketContext {
+    pub moves: Vec<MarketMove>,
+    // The whole market moved beyond the configured threshold
+    pub moving: bool,
+}
+
+impl MarketContext {
+    pub fn is_moving(&self, threshold_percent: Decimal) -> bool {
+        self.moves
+            .iter()
+            .flat_map(|item| item.changes.iter())
+            .any(|item| item.change.abs() >= threshold_percent)
+    }
+}
+
+// Stored data which complements the margin update in the report
+#[derive(Debug, Default)]
+pub struct MarginContext {
+    // Newest first
+    pub interest_rate_history: Vec<InterestRatePoint>,
+    pub isolated_pairs: Vec<IsolatedMarginData>,
+    // The update has base borrowings of isolated pairs, the asset is not on cross margin
+    pub isolated_only: bool,
 }
 
 pub struct MarginDataReport {
@@ -36,28 +90,138 @@ pub struct MarginDataReport {
     pub total_repay: Decimal,
     pub total_repay_usdt: Decimal,
     pub borrow_change: Decimal,
+    // Borrow change in standard deviations of the asset
+    pub borrow_z_score: Option<f64>,
     pub repay_change: Decimal,
     pub br_ratio: Decimal,
     pub available: Decimal,
+    // The highest available inventory over the inventory window
+    pub max_available: Option<Decimal>,
+    pub interest_rate: Option<InterestRateReport>,
+    pub isolated: Vec<IsolatedMarginData>,
+    pub isolated_only: bool,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct InterestRateReport {
+    pub hourly_rate: Decimal,
+    // Compared to the previous update
+    pub change: Option<Decimal>,
+    // Compared to the oldest rate in the history, which is that many hours old
+    pub history_change: Option<Decimal>,
+    pub history_hours: i64,
 }
 
 #[derive(Debug)]
 pub struct SpotReport {
+    // Prices and quote volumes are converted to USD if the pair is quoted in another asset
+    pub pair: SpotPair,
+    pub price: Option<PriceReport>,
     pub volume_change: Vec<AggregatedVolume>,
+    // Interval volume divided by the average volume of the same interval
+    pub relative_volume: Vec<VolumeRatio>,
     pub daily_volume: Option<BinanceDailyVolume>,
+    pub order_book: Option<OrderBookReport>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct OrderBookReport {
+    pub mid_price: Decimal,
+    pub spread_bps: Decimal,
+    pub depth: Vec<OrderBookDepth>,
+}
+
+// Bid and ask depth in quote asset within the percent around the mid price
+#[derive(Debug, PartialEq, Eq)]
+pub struct OrderBookDepth {
+    pub percent: Decimal,
+    pub bids: Decimal,
+    pub asks: Decimal,
+    // From -1 (only asks) to 1 (only bids)
+    pub imbalance: Decimal,
+}
+
+// High, low and VWAP are calculated for the last 4 hours
+#[derive(Debug, PartialEq, Eq)]
+pub struct PriceReport {
+    pub current: Decimal,
+    pub changes: Vec<PriceChange>,
+    pub high: Decimal,
+    pub low: Decimal,
+    // Distance between high and low, in percents
+    pub range: Decimal,
+    pub vwap: Decimal,
+}
+
+impl PriceReport {
+    pub fn round_to_tick_size(&mut self, tick_size: Decimal) {
+        let scale = tick_size.normalize().scale();
+        let round = |num: Decimal| num.round_dp(scale).normalize();
+
+        self.current = round(self.current);
+        self.high = round(self.high);
+        self.low = round(self.low);
+        self.vwap = round(self.vwap);
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct PriceChange {
+    pub interval: Interval,
+    pub change: Decimal,
 }
 
 #[derive(Debug)]
 pub struct FuturesReport {
+    pub contract: FuturesContract,
     pub funding_rate: Option<FundingRateReport>,
+    pub basis: Option<BasisReport>,
+    pub volume_change: Vec<AggregatedVolume>,
+    // Futures taker volume divided by spot taker volume
+    pub futures_spot_ratio: Vec<VolumeRatio>,
+    // Ratio of all accounts
     pub long_short_ratio: Vec<LongShortRatioReport>,
+    pub top_position_ratio: Vec<LongShortRatioReport>,
+    pub top_account_ratio: Vec<LongShortRatioReport>,
+    pub current_open_interest: Option<CurrentOpenInterest>,
     pub open_interest: Vec<OpenInterestChange>,
+    // None if the liquidations stream is disconnected
+    pub liquidations: Option<Vec<LiquidationVolume>>,
+}
+
+// Liquidated positions in USD
+#[derive(Debug, PartialEq, Eq)]
+pub struct LiquidationVolume {
+    pub interval: Interval,
+    pub long: Decimal,
+    pub short: Decimal,
+}
+
+// The latest open interest, amount is in base asset units
+#[derive(Debug, PartialEq, Eq)]
+pub struct CurrentOpenInterest {
+    pub value: Decimal,
+    pub amount: Decimal,
 }
 
 #[derive(Debug)]
 pub struct FundingRateReport {
     pub funding_rate: Decimal,
     pub next_funding_time: TimeDifference,
+    // The newest settled rates go first
+    pub history: Vec<Decimal>,
+    pub cumulative_24h: Decimal,
+    // Current rate annualized, in percents
+    pub annualized: Decimal,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct BasisReport {
+    pub mark_price: Decimal,
+    pub index_price: Decimal,
+    // Perpetual mark price minus spot index price
+    pub basis: Decimal,
+    pub premium_bps: Decimal,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -83,6 +247,10 @@ impl Interval {
     fn len(&self) -> usize {
         self.index() + 1
     }
+
+    fn duration(&self) -> Duration {
+        Duration::minutes(5 * self.index() as i64)
+    }
 }
 
 impl Display for Interval {
@@ -119,6 +287,12 @@ impl AggregatedVolume {
     }
 }
 
+#[derive(Debug, PartialEq, Eq)]
+pub struct VolumeRatio {
+    pub interval: Interval,
+    pub ratio: Decimal,
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct LongShortRatioReport {
     pub interval: Interval,
@@ -132,18 +306,18 @@ pub struct OpenInterestChange {
 }
 
 fn filter_sort_candles_volumes(
-    volumes: Vec<BinanceCandleMarketTradeVolume>,
-) -> Vec<BinanceCandleMarketTradeVolume> {
+    volumes: Vec<BinanceCandle>,
+) -> Vec<BinanceCandle> {
     let mut volumes = volumes
         .into_iter()
         .filter(|vol| vol.is_closed)
         .collect::<Vec<_>>();
 
-    volumes.sort_by(|item1, item2| item2.open_time.cmp(&item1.open_time));
+    volumes.sort_by_key(|item| Reverse(item.open_time));
     volumes
 }
 
-fn calculate_volume_changes(volumes: Vec<BinanceCandleMarketTradeVolume>) -> Vec<AggregatedVolume> {
+fn calculate_volume_changes(volumes: Vec<BinanceCandle>) -> Vec<AggregatedVolume> {
     let volumes = filter_sort_candles_volumes(volumes);
 
     INTERVALS
@@ -154,8 +328,8 @@ fn calculate_volume_changes(volumes: Vec<BinanceCandleMarketTradeVolume>) -> Vec
         })
         .map(|(interval, volumes)| {
             let sell = volumes.iter().map(|item| item.sell_quote_volume).sum();
-            let buy = volumes.iter().map(|item| item.buy_quote_volume).sum();
-            let buy_sell_ratio = buy / sell;
+            let buy: Decimal = volumes.iter().map(|item| item.buy_quote_volume).sum();
+            let buy_sell_ratio = buy.checked_div(sell).unwrap_or_default();
 
             let mut result = AggregatedVolume {
                 interval: *interval,
@@ -170,11 +344,230 @@ fn calculate_volume_changes(volumes: Vec<BinanceCandleMarketTradeVolume>) -> Vec
         .collect()
 }
 
+fn calculate_price_report(candles: &[BinanceCandle]) -> Option<PriceReport> {
+    let candles = filter_sort_candles_volumes(candles.to_vec());
+    let current = candles.first()?.close;
+
+    // Interval index matches the number of 5-minute candles in it,
+    // so the change is measured from the open price of the oldest candle.
+    let changes = INTERVALS
+        .iter()
+        .filter_map(|interval| candles.get(interval.index() - 1).map(|candle| (interval, candle)))
+        .map(|(interval, candle)| PriceChange {
+            interval: *interval,
+            change: find_percentage_diff(current, candle.open),
+        })
+        .collect();
+
+    let window = &candles[..candles.len().min(Interval::H4.index())];
+    let high = window.iter().map(|candle| candle.high).max()?;
+    let low = window.iter().map(|candle| candle.low).min()?;
+
+    let quote_volume = window.iter().map(|candle| candle.quote_volume).sum::<Decimal>();
+    let volume = window.iter().map(|candle| candle.volume).sum::<Decimal>();
+    let vwap = quote_volume.checked_div(volume).unwrap_or(current);
+
+    let process = |num: Decimal| num.trunc_with_scale(8).normalize();
+
+    Some(PriceReport {
+        current: process(current),
+        changes,
+        high: process(high),
+        low: process(low),
+        range: find_percentage_diff(high, low),
+        vwap: process(vwap),
+    })
+}
+
+// The end of the window the volume changes are summed over, i.e. the close of the newest closed candle
+fn volume_window_end(candles: &[BinanceCandle]) -> Option<DateTime<Utc>> {
+    candles
+        .iter()
+        .filter(|candle| candle.is_closed)
+        .map(|candle| candle.close_time + Duration::milliseconds(1))
+        .max()
+}
+
+// Volume of the candles within the window, candles only partly inside of it are counted pro rata.
+// None when the candles don't cover the whole window
+fn window_volume(candles: &[&BinanceCandle], start: DateTime<Utc>, end: DateTime<Utc>) -> Option<Decimal> {
+    let mut covered = 0;
+    let mut volume = Decimal::ZERO;
+
+    for candle in candles {
+        // Close time is the last millisecond of the candle
+        let candle_end = candle.close_time + Duration::milliseconds(1);
+        let overlap = (end.min(candle_end) - start.max(candle.open_time)).num_milliseconds();
+        if overlap <= 0 {
+            continue;
+        }
+
+        let length = (candle_end - candle.open_time).num_milliseconds();
+        covered += overlap;
+        volume += candle.quote_volume * Decimal::from(overlap) / Decimal::from(length);
+    }
+
+    (covered == (end - start).num_milliseconds()).then_some(volume)
+}
+
+// Baseline of every interval is the average volume of the same time of day window over the last days,
+// so the volume during the busiest hours is compared with the same hours, not with the quiet night ones.
+// Days the baseline candles don't fully cover are skipped
+fn calculate_relative_volume(
+    volumes: &[AggregatedVolume],
+    baseline: &[BinanceCandle],
+    end: DateTime<Utc>,
+    days: u32,
+) -> Vec<VolumeRatio> {
+    let baseline = baseline.iter().filter(|candle| candle.is_closed).collect::<Vec<_>>();
+
+    volumes
+        .iter()
+        .filter_map(|volume| {
+            let duration = Duration::minutes(5 * volume.interval.len() as i64);
+            let day_volumes = (1..=i64::from(days))
+                .filter_map(|day| {
+                    let day_end = end - Duration::days(day);
+                    window_volume(&baseline, day_end - duration, day_end)
+                })
+                .collect::<Vec<_>>();
+
+            let average = day_volumes
+                .iter()
+                .sum::<Decimal>()
+                .checked_div(Decimal::from(day_volumes.len()))?;
+            let ratio = (volume.buy + volume.sell).checked_div(average)?;
+
+            Some(VolumeRatio {
+                interval: volume.interval,
+                ratio: ratio.trunc_with_scale(2).normalize(),
+            })
+        })
+        .collect()
+}
+
+fn calculate_order_book_report(book: &BinanceOrderBook) -> Option<OrderBookReport> {
+    let [best_bid, _] = book.bids.first()?;
+    let [best_ask, _] = book.asks.first()?;
+
+    let mid_price = (best_bid + best_ask) / Decimal::TWO;
+    let spread_bps = (best_ask - best_bid).checked_div(mid_price)? * Decimal::from(10_000);
+
+    let sum_within = |levels: &[[Decimal; 2]], from: Decimal, to: Decimal| {
+        levels
+            .iter()
+            .filter(|[price, _]| *price >= from && *price <= to)
+            .map(|[price, quantity]| price * quantity)
+            .sum::<Decimal>()
+    };
+
+    let depth = DEPTH_BANDS
+        .iter()
+        .map(|percent| {
+            let percent = Decimal::from(*percent);
+            let band = mid_price * percent / Decimal::ONE_HUNDRED;
+
+            let bids = sum_within(&book.bids, mid_price - band, mid_price);
+            let asks = sum_within(&book.asks, mid_price, mid_price + band);
+            let imbalance = (bids - asks).checked_div(bids + asks).unwrap_or_default();
+
+            OrderBookDepth {
+                percent,
+                bids: bids.trunc_with_scale(2).normalize(),
+                asks: asks.trunc_with_scale(2).normalize(),
+                imbalance: imbalance.trunc_with_scale(2).normalize(),
+            }
+        })
+        .collect();
+
+    Some(OrderBookReport {
+        mid_price: mid_price.normalize(),
+        spread_bps: spread_bps.trunc_with_scale(2).normalize(),
+        depth,
+    })
+}
+
+fn calculate_futures_spot_ratio(
+    spot: &[AggregatedVolume],
+    futures: &[AggregatedVolume],
+) -> Vec<VolumeRatio> {
+    futures
+        .iter()
+        .filter_map(|futures| {
+            let spot = spot.iter().find(|spot| spot.interval == futures.interval)?;
+            let ratio = (futures.buy + futures.sell).checked_div(spot.buy + spot.sell)?;
+
+            Some(VolumeRatio {
+                interval: futures.interval,
+                ratio: ratio.trunc_with_scale(2).normalize(),
+            })
+        })
+        .collect()
+}
+
+// Intervals which started before the stream was connected are skipped as incomplete
+fn calculate_liquidations(feed: &LiquidationsFeed, now: DateTime<Utc>) -> Vec<LiquidationVolume> {
+    INTERVALS
+        .iter()
+        .filter(|interval| now - interval.duration() >= feed.since)
+        .map(|interval| {
+            let start = now - interval.duration();
+            let sum = |side: LiquidationSide| {
+                feed.liquidations
+                    .iter()
+                    .filter(|item| item.side == side && item.time > start)
+                    .map(|item| item.usd)
+                    .sum::<Decimal>()
+                    .trunc_with_scale(2)
+                    .normalize()
+            };
+
+            LiquidationVolume {
+                interval: *interval,
+                long: sum(LiquidationSide::Long),
+                short: sum(LiquidationSide::Short),
+            }
+        })
+        .collect()
+}
+
+// History goes from the newest to the oldest rate
+fn calculate_interest_rate_report(
+    update: &MarginDataUpdated,
+    history: &[InterestRatePoint],
+    now: DateTime<Utc>,
+) -> Option<InterestRateReport> {
+    let hourly_rate = update.new.hourly_interest_rate?;
+
+    let oldest = history.last().filter(|oldest| !oldest.hourly_rate.is_zero());
+    let history_change = oldest.map(|oldest| find_percentage_diff(hourly_rate, oldest.hourly_rate));
+    let history_hours = oldest.map(|oldest| (now - oldest.time).num_hours()).unwrap_or_default();
+
+    Some(InterestRateReport {
+        hourly_rate,
+        change: update.interest_rate_change(),
+        history_change,
+        history_hours,
+    })
+}
+
+fn calculate_current_open_interest(
+    open_interest: &[BinanceOpenInterest],
+    contract: &FuturesContract,
+) -> Option<CurrentOpenInterest> {
+    let recent = open_interest.iter().max_by_key(|item| item.datetime)?;
+
+    Some(CurrentOpenInterest {
+        value: recent.sum_open_interest_value.trunc_with_scale(2).normalize(),
+        amount: contract.to_base_amount(recent.sum_open_interest).trunc_with_scale(2).normalize(),
+    })
+}
+
 fn calculate_open_interest_changes(
     mut open_interest: Vec<BinanceOpenInterest>,
 ) -> Vec<OpenInterestChange> {
     // ensure that order is correct and the newest open interest goes first
-    open_interest.sort_by(|item1, item2| item2.datetime.cmp(&item1.datetime));
+    open_interest.sort_by_key(|item| Reverse(item.datetime));
 
     let Some(recent) = open_interest.first() else {
         return Vec::new();
@@ -198,199 +591,426 @@ fn get_long_short_ratios(
     mut ratios: Vec<BinanceLongShortRatioPositions>,
 ) -> Vec<LongShortRatioReport> {
     // ensure that order is correct and the newest ratios go first
-    ratios.sort_by(|item1, item2| item2.datetime.cmp(&item1.datetime));
+    ratios.sort_by_key(|item| Reverse(item.datetime));
 
     let mut intervals = vec![Interval::Now];
     intervals.extend_from_slice(&INTERVALS);
 
-    let data = intervals
+    intervals
         .iter()
         .filter_map(|interval| ratios.get(interval.index()).map(|ratio| (interval, ratio)))
         .map(|(interval, ratio)| LongShortRatioReport {
             interval: *interval,
             ratio: ratio.long_short_ratio.trunc_with_scale(2).normalize(),
         })
-        .collect::<Vec<_>>();
+        .collect::<Vec<_>>()
+}
+
+fn calculate_basis(rate: &BinanceFundingRate) -> Option<BasisReport> {
+    let basis = rate.mark_price - rate.index_price;
+    let premium_bps = basis.checked_div(rate.index_price)? * Decimal::from(10_000);
+
+    Some(BasisReport {
+        mark_price: rate.mark_price.normalize(),
+        index_price: rate.index_price.normalize(),
+        basis: basis.normalize(),
+        premium_bps: premium_bps.trunc_with_scale(2).normalize(),
+    })
+}
+
+fn build_funding_rate_report(
+    rate: BinanceFundingRate,
+    mut history: Vec<BinanceFundingRateHistory>,
+    now: DateTime<Utc>,
+) -> FundingRateReport {
+    // ensure that order is correct and the newest rates go first
+    history.sort_by_key(|item| Reverse(item.funding_time));
 
-    data
+    let day_ago = now - Duration::hours(24);
+    let cumulative_24h = history
+        .iter()
+        .filter(|item| item.funding_time > day_ago)
+        .map(|item| item.funding_rate)
+        .sum::<Decimal>();
+
+    // Funding interval differs between contracts, so it is taken from the last settlements.
+    // Settlement times are a few milliseconds off, so the gap is rounded to the nearest hour.
+    let interval_hours = match history.as_slice() {
+        [newest, previous, ..] => ((newest.funding_time - previous.funding_time).num_minutes() + 30) / 60,
+        _ => DEFAULT_FUNDING_INTERVAL_HOURS,
+    };
+    let interval_hours = if interval_hours > 0 { interval_hours } else { DEFAULT_FUNDING_INTERVAL_HOURS };
+    let periods_per_year = Decimal::from(365 * 24 / interval_hours);
+    let annualized = rate.last_funding_rate * periods_per_year * Decimal::ONE_HUNDRED;
+
+    let next_funding_time = TimeDifference::calculate((rate.next_funding_time - now).num_minutes());
+    let process = |num: Decimal| num.trunc_with_scale(5).normalize();
+
+    FundingRateReport {
+        funding_rate: process(rate.last_funding_rate),
+        next_funding_time,
+        history: history
+            .iter()
+            .take(FUNDING_HISTORY_SHOWN)
+            .map(|item| process(item.funding_rate))
+            .collect(),
+        cumulative_24h: process(cumulative_24h),
+        annualized: annualized.trunc_with_scale(2).normalize(),
+    }
 }
 
 pub struct ReportCollector {
     binance: Binance,
-    futures_symbols: RwLock<HashSet<String>>,
+    symbols: Arc<SymbolRegistry>,
+    stream: Arc<MarketStream>,
+    squeeze_weights: SqueezeWeights,
+    relative_volume_days: u32,
 }
 
 impl ReportCollector {
-    pub fn new(binance: Binance) -> Self {
+    pub fn new(
+        binance: Binance,
+        symbols: Arc<SymbolRegistry>,
+        stream: Arc<MarketStream>,
+        squeeze_weights: SqueezeWeights,
+        relative_volume_days: u32,
+    ) -> Self {
         Self {
             binance,
-            futures_symbols: RwLock::new(HashSet::new()),
+            symbols,
+            stream,
+            squeeze_weights,
+            relative_volume_days,
         }
     }
-    async fn get_futures_exchange_info_pairs(&self) -> Result<HashSet<String>> {
-        let exch_info = self.binance.get_futures_exchange_info().await?;
-
-        let trading_symbols = exch_info
-            .symbols
-            .into_iter()
-            .filter(|item| &item.status == "TRADING" && &item.contract_type == "PERPETUAL")
-            .map(|item| item.symbol)
-            .collect::<HashSet<_>>()
-            .into_iter()
-            .collect::<HashSet<_>>();
-
-        Ok(trading_symbols)
-    }
-
-    async fn is_futures_symbol(&self, symbol: &str) -> bool {
-        let lock = self.futures_symbols.read().await;
-
-        if lock.contains(symbol) {
-            return true;
+    async fn get_candles(&self, symbol: &str) -> Vec<BinanceCandle> {
+        if let Some(candles) = self.stream.candles(Market::Spot, symbol).await {
+            return candles;
         }
 
-        false
+        match self.binance.get_candlesticks_market_volume(symbol).await {
+            Ok(candles) => {
+                self.stream.seed_candles(Market::Spot, symbol, &candles).await;
+                candles
+            }
+            Err(e) => {
+                error!("Failed to get klines data for {}: {}", symbol, e);
+                Vec::new()
+            }
+        }
     }
 
-    async fn get_market_volumes_statistics(&self, symbol: &str) -> Vec<AggregatedVolume> {
+    async fn get_baseline_candles(&self, symbol: &str) -> Vec<BinanceCandle> {
         self.binance
-            .get_candlesticks_market_volume(symbol)
+            .get_candlesticks_baseline(symbol, self.relative_volume_days)
             .await
-            .map(|data| calculate_volume_changes(data))
             .unwrap_or_else(|e| {
-                error!("Failed to get klines data for {}: {}", symbol, e);
+                error!("Failed to get baseline klines data for {}: {}", symbol, e);
                 Vec::new()
             })
     }
 
-    async fn get_spot_daily_volume(&self, symbol: &str) -> Option<BinanceDailyVolume> {
-        match self.binance.get_spot_daily_volume(symbol).await {
-            Ok(volume) => Some(volume),
+    async fn get_futures_candles(&self, symbol: &str) -> Vec<BinanceCandle> {
+        if let Some(candles) = self.stream.candles(Market::Futures, symbol).await {
+            return candles;
+        }
+
+        match self.binance.get_futures_candlesticks_market_volume(symbol).await {
+            Ok(candles) => {
+                self.stream.seed_candles(Market::Futures, symbol, &candles).await;
+                candles
+            }
             Err(e) => {
-                error!("Failed to get spot daily volume for {}: {}", symbol, e);
+                error!("Failed to get futures klines data for {}: {}", symbol, e);
+                Vec::new()
+            }
+        }
+    }
+
+    async fn get_order_book(&self, pair: &SpotPair) -> Option<OrderBookReport> {
+        match self.binance.get_spot_order_book(&pair.symbol).await {
+            Ok(book) => calculate_order_book_report(&pair.convert_order_book(book)),
+            Err(e) => {
+                error!("Failed to get order book for {}: {}", pair.symbol, e);
                 None
             }
         }
     }
 
-    async fn get_funding_rate(&self, symbol: &str) -> Option<FundingRateReport> {
-        match self.binance.get_funding_rate(symbol).await {
-            Ok(rate) => {
-                let diff = rate.next_funding_time - Utc::now();
-                let diff = TimeDifference::calculate(diff.num_minutes());
+    async fn get_spot_daily_volume(&self, symbol: &str) -> Option<BinanceDailyVolume> {
+        if let Some(volume) = self.stream.ticker(symbol).await {
+            return Some(volume);
+        }
 
-                Some(FundingRateReport {
-                    funding_rate: rate.last_funding_rate.trunc_with_scale(5).normalize(),
-                    next_funding_time: diff,
-                })
-            }
+        match self.binance.get_spot_daily_volume(symbol).await {
+            Ok(volume) => Some(volume),
             Err(e) => {
-                error!("Failed to get funding rate for {}: {}", symbol, e);
+                error!("Failed to get spot daily volume for {}: {}", symbol, e);
                 None
             }
         }
     }
 
-    async fn get_open_interest_statistics(&self, symbol: &str) -> Vec<OpenInterestChange> {
+    async fn get_premium_index(&self, symbol: &str) -> Option<BinanceFundingRate> {
+        if let Some(rate) = self.stream.mark_price(symbol).await {
+            return Some(rate);
+        }
+
+        self.binance
+            .get_funding_rate(symbol)
+            .await
+            .map_err(|e| error!("Failed to get funding rate for {}: {}", symbol, e))
+            .ok()
+    }
+
+    async fn get_funding_rate(&self, symbol: &str, rate: BinanceFundingRate) -> FundingRateReport {
+        let history = self
+            .binance
+            .get_funding_rate_history(symbol)
+            .await
+            .unwrap_or_else(|e| {
+                error!("Failed to get funding rate history for {}: {}", symbol, e);
+                Vec::new()
+            });
+
+        build_funding_rate_report(rate, history, Utc::now())
+    }
+
+    async fn get_open_interest(&self, symbol: &str) -> Vec<BinanceOpenInterest> {
         self.binance
             .get_open_interest(symbol)
             .await
-            .map(|data| calculate_open_interest_changes(data))
             .unwrap_or_else(|e| {
                 error!("Failed to get OI for {}: {}", symbol, e);
                 Vec::new()
             })
     }
 
-    async fn get_long_short_ratio_statistics(&self, symbol: &str) -> Vec<LongShortRatioReport> {
+    async fn get_long_short_ratio(
+        &self,
+        symbol: &str,
+        kind: LongShortRatioKind,
+    ) -> Vec<BinanceLongShortRatioPositions> {
         self.binance
-            .get_long_short_ratio(symbol)
+            .get_long_short_ratio(symbol, kind)
             .await
-            .map(|data| get_long_short_ratios(data))
             .unwrap_or_else(|e| {
                 error!(
-                    "Failed to get long short positions ratio for {}: {}",
-                    symbol, e
+                    "Failed to get {:?} long short ratio for {}: {}",
+                    kind, symbol, e
                 );
                 Vec::new()
             })
     }
 
-    async fn build_spot_report(&self, symbol: &str) -> SpotReport {
-        let daily_volume = self.get_spot_daily_volume(symbol).await;
-        let volume_change = self.get_market_volumes_statistics(symbol).await;
+    async fn build_spot_report(&self, pair: SpotPair, chart: &mut ChartData) -> SpotReport {
+        let symbol = pair.symbol.as_str();
+
+        let daily_volume = self
+            .get_spot_daily_volume(symbol)
+            .await
+            .map(|volume| pair.convert_daily_volume(volume));
+
+        let candles = pair.convert_candles(self.get_candles(symbol).await);
+        chart.set_candles(&candles);
+        let mut price = calculate_price_report(&candles);
+        let window_end = volume_window_end(&candles);
+        let volume_change = calculate_volume_changes(candles);
+
+        // Tick size of the pair doesn't fit prices converted to USD
+        if let Some(price) = price.as_mut()
+            && pair.is_usd_quoted()
+            && let Some(tick_size) = self.symbols.spot_symbol(symbol).await.and_then(|info| info.tick_size)
+        {
+            price.round_to_tick_size(tick_size);
+        }
+
+        let baseline = pair.convert_candles(self.get_baseline_candles(symbol).await);
+        let relative_volume = window_end
+            .map(|end| calculate_relative_volume(&volume_change, &baseline, end, self.relative_volume_days))
+            .unwrap_or_default();
+
+        let order_book = self.get_order_book(&pair).await;
 
         SpotReport {
+            pair,
+            order_book,
+            price,
+            relative_volume,
             daily_volume,
             volume_change,
         }
     }
 
-    async fn build_futures_report(&self, symbol: &str) -> Option<FuturesReport> {
-        if !self.is_futures_symbol(symbol).await {
-            return None;
-        }
+    async fn build_futures_report(&self, asset: &str, chart: &mut ChartData) -> Option<FuturesReport> {
+        let contract = self.symbols.futures_contract(asset).await?;
+        let symbol = contract.info.symbol.as_str();
+
+        let premium_index = self
+            .get_premium_index(symbol)
+            .await
+            .map(|rate| contract.normalize_premium_index(rate));
+        let basis = premium_index.as_ref().and_then(calculate_basis);
+
+        let funding_rate = match premium_index {
+            Some(rate) => Some(self.get_funding_rate(symbol, rate).await),
+            None => None,
+        };
+
+        let ratios = self.get_long_short_ratio(symbol, LongShortRatioKind::GlobalAccount).await;
+        chart.set_long_short_ratio(&ratios);
+        let long_short_ratio = get_long_short_ratios(ratios);
+
+        let ratios = self.get_long_short_ratio(symbol, LongShortRatioKind::TopPosition).await;
+        let top_position_ratio = get_long_short_ratios(ratios);
+
+        let ratios = self.get_long_short_ratio(symbol, LongShortRatioKind::TopAccount).await;
+        let top_account_ratio = get_long_short_ratios(ratios);
 
-        let funding_rate = self.get_funding_rate(symbol).await;
-        let long_short_ratio = self.get_long_short_ratio_statistics(symbol).await;
-        let open_interest = self.get_open_interest_statistics(symbol).await;
+        let open_interest = self.get_open_interest(symbol).await;
+        chart.set_open_interest(&open_interest);
+        let current_open_interest = calculate_current_open_interest(&open_interest, &contract);
+        let open_interest = calculate_open_interest_changes(open_interest);
+
+        let candles = contract.normalize_candles(self.get_futures_candles(symbol).await);
+        let volume_change = calculate_volume_changes(candles);
+
+        let liquidations = self
+            .stream
+            .liquidations(symbol)
+            .await
+            .map(|feed| calculate_liquidations(&feed, Utc::now()));
 
         Some(FuturesReport {
+            contract,
             funding_rate,
+            basis,
+            volume_change,
+            futures_spot_ratio: Vec::new(),
             long_short_ratio,
+            top_position_ratio,
+            top_account_ratio,
+            current_open_interest,
             open_interest,
+            liquidations,
         })
     }
 
-    fn build_margin_data_report(&self, margin_update: MarginDataUpdated) -> MarginDataReport {
+    pub fn build_margin_data_report(
+        &self,
+        margin_update: MarginDataUpdated,
+        context: MarginContext,
+    ) -> MarginDataReport {
+        let interest_rate = calculate_interest_rate_report(&margin_update, &context.interest_rate_history, Utc::now());
+
+        let mut isolated = context.isolated_pairs;
+        isolated.sort_by_key(|pair| Reverse(pair.base.total_borrow_in_usdt));
+
         MarginDataReport {
+            interest_rate,
+            isolated,
+            isolated_only: context.isolated_only,
             total_borrow: margin_update.new.total_borrow,
             total_borrow_usdt: margin_update.new.total_borrow_in_usdt,
             total_repay: margin_update.new.total_repay,
             total_repay_usdt: margin_update.new.total_repay_in_usdt,
             borrow_change: margin_update.borrow_change(),
+            borrow_z_score: margin_update.borrow_z_score,
             repay_change: margin_update.repay_change(),
             br_ratio: margin_update.borrow_repay_ratio(),
             available: margin_update.new.available,
+            max_available: margin_update.max_available,
+        }
+    }
+
+    fn calculate_squeeze_score(
+        &self,
+        margin_data: &MarginDataReport,
+        futures: Option<&FuturesReport>,
+    ) -> SqueezeScore {
+        let inputs = SqueezeInputs {
+            margin_data,
+            funding_rate: futures.and_then(|report| report.funding_rate.as_ref()),
+            open_interest: futures.map(|report| report.open_interest.as_slice()).unwrap_or_default(),
+            long_short_ratio: futures.map(|report| report.long_short_ratio.as_slice()).unwrap_or_default(),
+        };
+
+        calculate_squeeze_score(inputs, &self.squeeze_weights)
+    }
+
+    async fn build_market_context(&self) -> MarketContext {
+        let mut moves = Vec::with_capacity(MARKET_SYMBOLS.len());
+
+        for (asset, symbol) in MARKET_SYMBOLS {
+            let candles = self.get_candles(symbol).await;
+
+            if let Some(price) = calculate_price_report(&candles) {
+                moves.push(MarketMove { asset: asset.to_string(), changes: price.changes });
+            }
         }
+
+        MarketContext { moves, moving: false }
     }
 
-    pub async fn build_report(&self, margin_update: MarginDataUpdated) -> Report {
-        let symbol = margin_update.new.asset.clone();
-        let pair = format!("{}USDT", symbol);
+    // Spot and futures sections of the asset, futures to spot ratio needs both of them
+    async fn build_market_reports(
+        &self,
+        asset: &str,
+        chart: &mut ChartData,
+    ) -> (SpotReport, Option<FuturesReport>) {
+        let spot_pair = self.symbols.spot_pair(asset).await;
+        let spot = self.build_spot_report(spot_pair, chart).await;
+        let mut futures = self.build_futures_report(asset, chart).await;
+
+        if let Some(futures) = futures.as_mut() {
+            futures.futures_spot_ratio = calculate_futures_spot_ratio(&spot.volume_change, &futures.volume_change);
+        }
 
-        let margin_data = self.build_margin_data_report(margin_update);
-        let spot = self.build_spot_report(&pair).await;
-        let futures = self.build_futures_report(&pair).await;
+        (spot, futures)
+    }
+
+    pub fn max_squeeze_score(&self, margin_data: &MarginDataReport) -> Decimal {
+        max_squeeze_score(margin_data, &self.squeeze_weights)
+    }
+
+    // Margin section is built beforehand, so the report can be filtered by it
+    // before the market data is requested
+    pub async fn build_report(&self, symbol: String, margin_data: MarginDataReport, kind: SignalKind) -> Report {
+        let mut chart = ChartData::default();
+
+        let market = self.build_market_context().await;
+        let (spot, futures) = self.build_market_reports(&symbol, &mut chart).await;
+        let squeeze = self.calculate_squeeze_score(&margin_data, futures.as_ref());
 
         Report {
+            kind,
             symbol,
+            market,
             margin_data,
             spot,
             futures,
+            squeeze,
+            chart,
         }
     }
-}
 
-pub async fn periodic_futures_pairs_update(collector: Arc<ReportCollector>) {
-    let mut interval = interval(EXCHANGE_INFO_UPDATE_INTERVAL);
-    info!("Updating exchange info Binance Futures");
+    pub async fn build_new_asset_report(&self, data: MarginData) -> NewAssetReport {
+        let mut chart = ChartData::default();
+        let (spot, futures) = self.build_market_reports(&data.asset, &mut chart).await;
 
-    loop {
-        interval.tick().await;
-
-        match collector.get_futures_exchange_info_pairs().await {
-            Err(e) => error!("Failed to update exchange info Binance Futures: {}", e),
-            Ok(data) => {
-                let mut lock = collector.futures_symbols.write().await;
-                lock.extend(data);
-            }
+        NewAssetReport {
+            data,
+            spot,
+            futures,
+            chart,
         }
     }
 }
 
 #[cfg(test)]
 mod test {
+    use crate::stream::Liquidation;
+    use crate::symbols::SymbolInfo;
     use crate::utils::{candles_fixture, open_interest_fixture, position_ratio_fixture};
 
     use super::*;
@@ -535,6 +1155,117 @@ mod test {
         assert!(result.is_empty());
     }
 
+    #[test]
+    fn test_calculate_liquidations() {
+        let now = Utc::now();
+        let liquidation = |minutes_ago: i64, side: LiquidationSide, usd: i64| Liquidation {
+            time: now - Duration::minutes(minutes_ago),
+            side,
+            usd: Decimal::from(usd),
+        };
+
+        let feed = LiquidationsFeed {
+            since: now - Duration::minutes(90),
+            liquidations: vec![
+                liquidation(80, LiquidationSide::Short, 500),
+                liquidation(30, LiquidationSide::Long, 1000),
+                liquidation(10, LiquidationSide::Short, 200),
+                liquidation(2, LiquidationSide::Long, 100),
+            ],
+        };
+
+        let result = calculate_liquidations(&feed, now);
+
+        // 4h interval isn't covered by the stream yet
+        let expected = vec![
+            LiquidationVolume { interval: Interval::M5, long: Decimal::from(100), short: Decimal::ZERO },
+            LiquidationVolume { interval: Interval::M15, long: Decimal::from(100), short: Decimal::from(200) },
+            LiquidationVolume { interval: Interval::H1, long: Decimal::from(1100), short: Decimal::from(200) },
+        ];
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_market_context_is_moving() {
+        let market_move = |asset: &str, changes: [i64; 2]| MarketMove {
+            asset: asset.to_string(),
+            changes: vec![
+                PriceChange { interval: Interval::M5, change: Decimal::from(changes[0]) },
+                PriceChange { interval: Interval::H1, change: Decimal::from(changes[1]) },
+            ],
+        };
+
+        let context = MarketContext {
+            moves: vec![market_move("BTC", [0, 1]), market_move("ETH", [1, -3])],
+            moving: false,
+        };
+
+        assert!(context.is_moving(Decimal::from(3)));
+        assert!(!context.is_moving(Decimal::from(4)));
+        assert!(!MarketContext::default().is_moving(Decimal::ONE));
+    }
+
+    #[test]
+    fn test_calculate_interest_rate_report() {
+        let now = Utc::now();
+        let rate = |value: i64| Some(Decimal::new(value, 6));
+        let point = |hours_ago: i64, value: i64| InterestRatePoint {
+            time: now - Duration::hours(hours_ago),
+            hourly_rate: Decimal::new(value, 6),
+        };
+
+        let update = MarginDataUpdated {
+            old: MarginData { hourly_interest_rate: rate(4), ..Default::default() },
+            new: MarginData { hourly_interest_rate: rate(6), ..Default::default() },
+            borrow_z_score: None,
+            max_available: None,
+        };
+
+        let history = vec![point(0, 6), point(1, 4), point(24, 2)];
+        let expected = InterestRateReport {
+            hourly_rate: Decimal::new(6, 6),
+            change: Some(Decimal::from(50)),
+            history_change: Some(Decimal::from(200)),
+            history_hours: 24,
+        };
+        assert_eq!(calculate_interest_rate_report(&update, &history, now), Some(expected));
+
+        let result = calculate_interest_rate_report(&update, &[], now).unwrap();
+        assert_eq!(result.history_change, None);
+
+        let update = MarginDataUpdated {
+            new: MarginData { hourly_interest_rate: None, ..Default::default() },
+            ..update
+        };
+        assert_eq!(calculate_interest_rate_report(&update, &history, now), None);
+    }
+
+    #[test]
+    fn test_calculate_current_open_interest() {
+        let contract = FuturesContract {
+            info: SymbolInfo {
+                symbol: "1000SOLUSDT".to_string(),
+                base_asset: "1000SOL".to_string(),
+                quote_asset: "USDT".to_string(),
+                tick_size: None,
+                onboard_date: None,
+            },
+            multiplier: Decimal::ONE_THOUSAND,
+        };
+
+        let result = calculate_current_open_interest(&open_interest_fixture(), &contract);
+
+        let expected = CurrentOpenInterest {
+            value: Decimal::new(8178516712, 2),
+            amount: Decimal::from(459_131_910),
+        };
+        assert_eq!(result, Some(expected));
+
+        let result = calculate_current_open_interest(&[], &contract);
+        assert!(result.is_none());
+    }
+
     #[test]
     fn test_calculate_open_interest_changes() {
         let oi = open_interest_fixture();
@@ -580,4 +1311,227 @@ mod test {
         let result = calculate_open_interest_changes(Vec::new());
         assert!(result.is_empty());
     }
+
+    #[test]
+    fn test_build_funding_rate_report() {
+        let now = "2025-10-17T10:00:00Z".parse::<DateTime<Utc>>().unwrap();
+
+        let rate = BinanceFundingRate {
+            mark_price: Decimal::ONE,
+            index_price: Decimal::ONE,
+            last_funding_rate: Decimal::new(-5, 4),
+            next_funding_time: "2025-10-17T12:00:00Z".parse().unwrap(),
+        };
+
+        let settlements = [
+            "2025-10-16T04:00:00Z",
+            "2025-10-16T12:00:00Z",
+            "2025-10-16T20:00:00Z",
+            "2025-10-17T04:00:00Z",
+        ];
+
+        let history = settlements
+            .into_iter()
+            .zip([1, -2, -3, -4])
+            .map(|(time, rate)| BinanceFundingRateHistory {
+                funding_rate: Decimal::new(rate, 4),
+                funding_time: time.parse().unwrap(),
+            })
+            .collect::<Vec<_>>();
+
+        let result = build_funding_rate_report(rate.clone(), history, now);
+
+        assert_eq!(result.funding_rate, Decimal::new(-5, 4));
+        assert_eq!(result.next_funding_time, TimeDifference::calculate(120));
+        assert_eq!(result.history, vec![Decimal::new(-4, 4), Decimal::new(-3, 4), Decimal::new(-2, 4)]);
+        assert_eq!(result.cumulative_24h, Decimal::new(-9, 4));
+        // 3 settlements a day
+        assert_eq!(result.annualized, Decimal::new(-5475, 2));
+
+        // settlements a few milliseconds under 8 hours apart
+        let history = ["2025-10-16T20:00:00.004Z", "2025-10-17T03:59:59.999Z"]
+            .into_iter()
+            .map(|time| BinanceFundingRateHistory {
+                funding_rate: Decimal::new(-4, 4),
+                funding_time: time.parse().unwrap(),
+            })
+            .collect::<Vec<_>>();
+
+        let result = build_funding_rate_report(rate, history, now);
+        assert_eq!(result.annualized, Decimal::new(-5475, 2));
+    }
+
+    #[test]
+    fn test_calculate_basis() {
+        let rate = BinanceFundingRate {
+            mark_price: Decimal::new(17820, 2),
+            index_price: Decimal::new(17900, 2),
+            last_funding_rate: Decimal::ZERO,
+            next_funding_time: Utc::now(),
+        };
+
+        let expected = BasisReport {
+            mark_price: Decimal::new(1782, 1),
+            index_price: Decimal::from(179),
+            basis: Decimal::new(-8, 1),
+            premium_bps: Decimal::new(-4469, 2),
+        };
+        assert_eq!(calculate_basis(&rate), Some(expected));
+
+        let rate = BinanceFundingRate {
+            index_price: Decimal::ZERO,
+            ..rate
+        };
+        assert_eq!(calculate_basis(&rate), None);
+    }
+
+    #[test]
+    fn test_calculate_futures_spot_ratio() {
+        let volume = |interval, buy, sell| AggregatedVolume {
+            interval,
+            buy: Decimal::from(buy),
+            sell: Decimal::from(sell),
+            buy_sell_ratio: Decimal::ONE,
+        };
+
+        let spot = vec![
+            volume(Interval::M5, 100, 100),
+            volume(Interval::M15, 300, 0),
+            volume(Interval::H1, 0, 0),
+        ];
+
+        let futures = vec![
+            volume(Interval::M5, 300, 400),
+            volume(Interval::M15, 500, 500),
+            volume(Interval::H1, 100, 100),
+            volume(Interval::H4, 100, 100),
+        ];
+
+        let result = calculate_futures_spot_ratio(&spot, &futures);
+
+        let expected = vec![
+            VolumeRatio { interval: Interval::M5, ratio: Decimal::new(35, 1) },
+            VolumeRatio { interval: Interval::M15, ratio: Decimal::new(333, 2) },
+        ];
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_calculate_price_report() {
+        let candles = candles_fixture();
+        let result = calculate_price_report(&candles).unwrap();
+
+        let expected = PriceReport {
+            current: Decimal::new(18148, 2),
+            changes: vec![
+                PriceChange { interval: Interval::M5, change: Decimal::new(34, 2) },
+                PriceChange { interval: Interval::M15, change: Decimal::new(-6, 2) },
+                PriceChange { interval: Interval::H1, change: Decimal::new(325, 2) },
+                PriceChange { interval: Interval::H4, change: Decimal::new(14, 1) },
+            ],
+            high: Decimal::new(1827, 1),
+            low: Decimal::new(17409, 2),
+            range: Decimal::new(494, 2),
+            vwap: Decimal::new(17812927155, 8),
+        };
+
+        assert_eq!(result, expected);
+
+        let result = calculate_price_report(&[]);
+        assert!(result.is_none());
+
+        let mut result = expected;
+        result.round_to_tick_size(Decimal::new(10, 2));
+        assert_eq!(result.vwap, Decimal::new(1781, 1));
+        assert_eq!(result.low, Decimal::new(1741, 1));
+    }
+
+    #[test]
+    fn test_calculate_relative_volume() {
+        let start = DateTime::parse_from_rfc3339("2024-01-07T00:00:00Z").unwrap().to_utc();
+
+        // Three days of hourly candles, 10:00 - 11:00 is ten times busier than the other hours
+        let baseline = (0..80)
+            .map(|hour| {
+                let open_time = start + Duration::hours(hour);
+                let quote_volume = if hour % 24 == 10 { Decimal::from(1200) } else { Decimal::from(120) };
+
+                BinanceCandle {
+                    open_time,
+                    open: Decimal::ONE,
+                    high: Decimal::ONE,
+                    low: Decimal::ONE,
+                    close: Decimal::ONE,
+                    volume: quote_volume,
+                    close_time: open_time + Duration::hours(1) - Duration::milliseconds(1),
+                    is_closed: true,
+                    quote_volume,
+                    buy_base_volume: Decimal::ZERO,
+                    sell_quote_volume: Decimal::ZERO,
+                    buy_quote_volume: Decimal::ZERO,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let volume = |interval: Interval, buy: i64, sell: i64| AggregatedVolume {
+            interval,
+            sell: Decimal::from(sell),
+            buy: Decimal::from(buy),
+            buy_sell_ratio: Decimal::ZERO,
+        };
+
+        // 10:20 - 10:30 had 200 volume each day and 06:25 - 10:30 had 1030
+        let volumes = vec![volume(Interval::M5, 150, 250), volume(Interval::H4, 1000, 1060)];
+        let end = DateTime::parse_from_rfc3339("2024-01-10T10:30:00Z").unwrap().to_utc();
+
+        let expected = vec![
+            VolumeRatio { interval: Interval::M5, ratio: Decimal::TWO },
+            VolumeRatio { interval: Interval::H4, ratio: Decimal::TWO },
+        ];
+
+        assert_eq!(calculate_relative_volume(&volumes, &baseline, end, 3), expected);
+
+        // The fourth day isn't in the baseline
+        assert_eq!(calculate_relative_volume(&volumes, &baseline, end, 4), expected);
+
+        let result = calculate_relative_volume(&volumes, &[], end, 3);
+        assert!(result.is_empty());
+    }
+
+    #[test]
+    fn test_calculate_order_book_report() {
+        let level = |price: i64, quantity: i64| [Decimal::new(price, 1), Decimal::from(quantity)];
+
+        let book = BinanceOrderBook {
+            bids: vec![level(999, 10), level(995, 20), level(985, 100), level(900, 1000)],
+            asks: vec![level(1001, 5), level(1009, 10), level(1015, 50), level(1100, 1000)],
+        };
+
+        let result = calculate_order_book_report(&book).unwrap();
+
+        let expected = OrderBookReport {
+            mid_price: Decimal::from(100),
+            spread_bps: Decimal::from(20),
+            depth: vec![
+                OrderBookDepth {
+                    percent: Decimal::ONE,
+                    bids: Decimal::from(2989),
+                    asks: Decimal::new(15095, 1),
+                    imbalance: Decimal::new(32, 2),
+                },
+                OrderBookDepth {
+                    percent: Decimal::TWO,
+                    bids: Decimal::from(12839),
+                    asks: Decimal::new(65845, 1),
+                    imbalance: Decimal::new(32, 2),
+                },
+            ],
+        };
+
+        assert_eq!(result, expected);
+
+        let book = BinanceOrderBook { bids: Vec::new(), asks: Vec::new() };
+        assert!(calculate_order_book_report(&book).is_none());
+    }
 }
@@ -4,20 +4,51 @@ use chrono::{DateTime, Utc};
 use log::{error, info};
 use tokio::sync::mpsc::Receiver;
 
+use crate::chart::{render_chart, render_leverage_chart, ChartData};
+use crate::config::{AnomalyConfig, InterestRateConfig, MarketMoveConfig, RepaySurgeConfig, ReportFilters};
+use crate::leverage::LeverageSummary;
 use crate::redis::Redis;
-use crate::report::ReportCollector;
-use crate::structs::{MarginDataMessage, MarginDataUpdated, TimeDifference};
-use crate::telegram::{format_full_report, format_new_margin_data_message, Telegram};
+use crate::report::{MarginContext, MarginDataReport, Report, ReportCollector};
+use crate::structs::{
+    InterestRatePoint,
+    IsolatedMarginData,
+    IsolatedMarginDataUpdated,
+    MarginData,
+    MarginDataMessage,
+    MarginDataUpdated,
+    SignalKind,
+    TimeDifference,
+};
+use crate::telegram::{
+    format_digest_message,
+    format_full_report,
+    format_futures_listing_message,
+    format_inventory_depletion_message,
+    format_leverage_summary,
+    format_new_asset_report,
+    format_removed_margin_data_message,
+    Telegram,
+};
+
+// Conditions which decide whether a report is built and sent
+pub struct SignalRules {
+    pub repay_surge: RepaySurgeConfig,
+    pub interest_rate: InterestRateConfig,
+    pub anomaly: AnomalyConfig,
+    pub filters: ReportFilters,
+    pub market_move: MarketMoveConfig,
+}
 
 pub struct ReportProcessor {
     report: Arc<ReportCollector>,
     redis: Arc<Redis>,
     tg: Telegram,
+    rules: SignalRules,
 }
 
 impl ReportProcessor {
-    pub fn new(report: Arc<ReportCollector>, redis: Arc<Redis>, tg: Telegram) -> Self {
-        Self { report, redis, tg }
+    pub fn new(report: Arc<ReportCollector>, redis: Arc<Redis>, tg: Telegram, rules: SignalRules) -> Self {
+        Self { report, redis, tg, rules }
     }
 
     async fn get_last_update_time(&self, symbol: &str) -> DateTime<Utc> {
@@ -40,26 +71,173 @@ impl ReportProcessor {
             .ok();
     }
 
-    async fn process_margin_data_update(&self, update: MarginDataUpdated) {
-        let condition_1m = update.is_more_than_1m() && update.is_percent_changed_enough();
+    async fn get_interest_rate_history(&self, symbol: &str) -> Vec<InterestRatePoint> {
+        self
+            .redis
+            .get_interest_rate_history(symbol)
+            .await
+            .map_err(|e| error!("Failed to get interest rate history for {}: {}", symbol, e))
+            .unwrap_or_default()
+    }
+
+    async fn get_isolated_pairs(&self, asset: &str) -> Vec<IsolatedMarginData> {
+        self
+            .redis
+            .get_isolated_margin_data(asset)
+            .await
+            .map_err(|e| error!("Failed to get isolated pairs for {}: {}", asset, e))
+            .unwrap_or_default()
+    }
+
+    // Size of the borrowings is checked before the interest rate history is read,
+    // so only the assets that can get a report cost a redis request
+    async fn get_signal_kind(&self, update: &MarginDataUpdated, now: DateTime<Utc>) -> Option<SignalKind> {
+        if update.is_borrow_anomaly(&self.rules.anomaly) && update.is_borrow_big_enough() {
+            return Some(SignalKind::Borrow);
+        }
+
+        if update.is_repay_surge(&self.rules.repay_surge) {
+            return Some(SignalKind::RepaySurge);
+        }
+
+        if update.is_interest_rate_tracked(&self.rules.interest_rate) {
+            let history = self.get_interest_rate_history(&update.new.asset).await;
+
+            if update.is_interest_rate_jump(&history, now, &self.rules.interest_rate) {
+                return Some(SignalKind::InterestRateJump);
+            }
+        }
+
+        None
+    }
+
+    // Drops borrow reports which can't reach the min squeeze score whatever the futures data is
+    fn passes_margin_filters(&self, kind: SignalKind, margin_data: &MarginDataReport) -> bool {
+        if kind != SignalKind::Borrow {
+            return true;
+        }
+
+        self.rules
+            .filters
+            .min_squeeze_score
+            .is_none_or(|min_score| self.report.max_squeeze_score(margin_data) >= min_score)
+    }
+
+    // Checks the report against the configured filters. Only borrow reports are filtered.
+    fn passes_filters(&self, report: &Report) -> bool {
+        if report.kind != SignalKind::Borrow {
+            return true;
+        }
+
+        if let Some(min_score) = self.rules.filters.min_squeeze_score
+            && report.squeeze.score < min_score
+        {
+            return false;
+        }
+
+        if let Some(min_relative_volume) = self.rules.filters.min_relative_volume {
+            let max_relative_volume = report.spot.relative_volume.iter().map(|item| item.ratio).max();
+
+            if max_relative_volume.is_none_or(|ratio| ratio < min_relative_volume) {
+                return false;
+            }
+        }
+
+        true
+    }
 
-        if (update.is_borrowing_rapidly_increased() || condition_1m) && update.is_borrow_big_enough() {
+    async fn process_margin_data_update(&self, update: MarginDataUpdated, isolated_only: bool) {
+        let now = Utc::now();
+
+        if let Some(kind) = self.get_signal_kind(&update, now).await {
             let asset = update.new.asset.clone();
-            let now = Utc::now();
 
             let last_update = self.get_last_update_time(&asset).await;
 
             let min_diff = (now - last_update).num_minutes();
             let time_diff = TimeDifference::calculate(min_diff);
 
-            info!("Building report for {}", asset);
-            let report = self.report.build_report(update).await;
+            info!("Building {:?} report for {}", kind, asset);
+            let context = MarginContext {
+                interest_rate_history: self.get_interest_rate_history(&asset).await,
+                isolated_pairs: self.get_isolated_pairs(&asset).await,
+                isolated_only,
+            };
+            let margin_data = self.report.build_margin_data_report(update, context);
+
+            if !self.passes_margin_filters(kind, &margin_data) {
+                info!("Report for {} can't reach the min squeeze score", asset);
+                return;
+            }
+
+            let mut report = self.report.build_report(asset.clone(), margin_data, kind).await;
+
+            if !self.passes_filters(&report) {
+                info!("Report for {} does not pass the filters", asset);
+                return;
+            }
+
+            if let Some(threshold) = self.rules.market_move.threshold_percent
+                && report.market.is_moving(threshold)
+            {
+                if self.rules.market_move.hold_signals {
+                    info!("Report for {} is held back during the market move", asset);
+                    return;
+                }
+
+                report.market.moving = true;
+            }
+
+            let chart = self.render_chart(&asset, &report.chart);
             let report = format_full_report(report, time_diff);
 
-            self.tg.send_message(&report).await;
+            self.send_report(&report, chart).await;
             self.save_last_update_time(&asset, now).await;
         }
     }
+
+    // Assets which are only on isolated margin are checked by base borrowings of their pairs
+    async fn process_isolated_update(&self, update: IsolatedMarginDataUpdated) {
+        self.process_margin_data_update(update.base_update(), true).await;
+    }
+
+    async fn process_new_asset(&self, data: MarginData) {
+        let asset = data.asset.clone();
+        let now = Utc::now();
+
+        info!("Building new asset report for {}", asset);
+        let report = self.report.build_new_asset_report(data).await;
+
+        let chart = self.render_chart(&asset, &report.chart);
+        let report = format_new_asset_report(report);
+
+        self.send_report(&report, chart).await;
+        self.save_last_update_time(&asset, now).await;
+    }
+
+    fn render_chart(&self, asset: &str, chart: &ChartData) -> Option<Vec<u8>> {
+        render_chart(chart)
+            .map_err(|e| error!("Failed to render chart for {}: {}", asset, e))
+            .ok()
+            .flatten()
+    }
+
+    async fn process_leverage_summary(&self, summary: LeverageSummary) {
+        let chart = render_leverage_chart(&summary.total_history, &summary.stablecoin_history)
+            .map_err(|e| error!("Failed to render leverage chart: {}", e))
+            .ok()
+            .flatten();
+
+        let report = format_leverage_summary(summary);
+        self.send_report(&report, chart).await;
+    }
+
+    async fn send_report(&self, report: &str, chart: Option<Vec<u8>>) {
+        match chart {
+            Some(chart) => self.tg.send_photo(report, chart).await,
+            None => self.tg.send_message(report).await,
+        }
+    }
 }
 
 pub async fn process_new_reports(
@@ -69,9 +247,24 @@ pub async fn process_new_reports(
     while let Some(event) = report_rx.recv().await {
         match event {
             MarginDataMessage::Error(e) => processor.tg.send_error_message(e).await,
-            MarginDataMessage::Update(update) => processor.process_margin_data_update(update).await,
-            MarginDataMessage::New(data) => {
-                let msg = format_new_margin_data_message(data);
+            MarginDataMessage::Update(update) => processor.process_margin_data_update(update, false).await,
+            MarginDataMessage::IsolatedUpdate(update) => processor.process_isolated_update(update).await,
+            MarginDataMessage::New(data) => processor.process_new_asset(data).await,
+            MarginDataMessage::Removed(data) => {
+                let msg = format_removed_margin_data_message(data);
+                processor.tg.send_message(&msg).await
+            }
+            MarginDataMessage::InventoryDepletion(depletion) => {
+                let msg = format_inventory_depletion_message(depletion);
+                processor.tg.send_message(&msg).await
+            }
+            MarginDataMessage::FuturesListing(listing) => {
+                let msg = format_futures_listing_message(listing);
+                processor.tg.send_message(&msg).await
+            }
+            MarginDataMessage::LeverageSummary(summary) => processor.process_leverage_summary(summary).await,
+            MarginDataMessage::Digest(digest) => {
+                let msg = format_digest_message(digest);
                 processor.tg.send_message(&msg).await
             }
         }
@@ -0,0 +1,312 @@
+use std::cmp::Reverse;
+use std::fmt::Display;
+
+use rust_decimal::Decimal;
+
+use crate::config::SqueezeWeights;
+use crate::report::{
+    FundingRateReport,
+    Interval,
+    LongShortRatioReport,
+    MarginDataReport,
+    OpenInterestChange,
+};
+
+const MAIN_FACTORS_NUMBER: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SqueezeFactor {
+    BorrowGrowth,
+    BrRatio,
+    Funding,
+    OpenInterest,
+    LongShortRatio,
+    Inventory,
+}
+
+impl Display for SqueezeFactor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let val = match self {
+            SqueezeFactor::BorrowGrowth => "borrow growth",
+            SqueezeFactor::BrRatio => "B/R ratio",
+            SqueezeFactor::Funding => "funding",
+            SqueezeFactor::OpenInterest => "OI",
+            SqueezeFactor::LongShortRatio => "long short ratio",
+            SqueezeFactor::Inventory => "inventory",
+        };
+
+        write!(f, "{}", val)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct SqueezeContribution {
+    pub factor: SqueezeFactor,
+    // Points out of 100 the factor added to the score
+    pub points: Decimal,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct SqueezeScore {
+    // From 0 to 100
+    pub score: Decimal,
+    // The biggest contributions in descending order
+    pub main_factors: Vec<SqueezeContribution>,
+}
+
+// Maps the value from [from, to] range to [0, 1]
+fn normalize(value: Decimal, from: Decimal, to: Decimal) -> Decimal {
+    ((value - from) / (to - from)).clamp(Decimal::ZERO, Decimal::ONE)
+}
+
+pub struct SqueezeInputs<'a> {
+    pub margin_data: &'a MarginDataReport,
+    pub funding_rate: Option<&'a FundingRateReport>,
+    pub open_interest: &'a [OpenInterestChange],
+    pub long_short_ratio: &'a [LongShortRatioReport],
+}
+
+impl SqueezeInputs<'_> {
+    // Every factor is normalized to [0, 1], where 1 is the strongest squeeze sign
+    fn factors(&self) -> Vec<(SqueezeFactor, Decimal)> {
+        let margin = self.margin_data;
+
+        // 100% growth and more gets the max value
+        let borrow_growth = normalize(margin.borrow_change, Decimal::ZERO, Decimal::ONE_HUNDRED);
+        let br_ratio = normalize(margin.br_ratio, Decimal::ONE, Decimal::TEN);
+
+        // Shorts paying 0.1% per period and more gets the max value
+        let funding = self
+            .funding_rate
+            .map(|rate| normalize(-rate.funding_rate, Decimal::ZERO, Decimal::new(1, 3)))
+            .unwrap_or_default();
+
+        // 10% hourly growth of OI gets the max value
+        let open_interest = self
+            .open_interest
+            .iter()
+            .find(|oi| oi.interval == Interval::H1)
+            .map(|oi| normalize(oi.change, Decimal::ZERO, Decimal::TEN))
+            .unwrap_or_default();
+
+        // Crowd leaning short. Ratio 0.5 and less gets the max value
+        let long_short_ratio = self
+            .long_short_ratio
+            .first()
+            .map(|ratio| Decimal::ONE - normalize(ratio.ratio, Decimal::new(5, 1), Decimal::new(15, 1)))
+            .unwrap_or_default();
+
+        // Share of the recent maximum inventory which has been taken out
+        let inventory = margin
+            .max_available
+            .filter(|max| !max.is_zero())
+            .map(|max| normalize(max - margin.available, Decimal::ZERO, max))
+            .unwrap_or_default();
+
+        let mut factors = vec![
+            (SqueezeFactor::BorrowGrowth, borrow_growth),
+            (SqueezeFactor::BrRatio, br_ratio),
+            (SqueezeFactor::Funding, funding),
+            (SqueezeFactor::OpenInterest, open_interest),
+            (SqueezeFactor::LongShortRatio, long_short_ratio),
+        ];
+
+        // Isolated pairs have no public inventory, the score is weighted without it
+        if !margin.isolated_only {
+            factors.push((SqueezeFactor::Inventory, inventory));
+        }
+
+        factors
+    }
+}
+
+fn factor_weight(weights: &SqueezeWeights, factor: SqueezeFactor) -> Decimal {
+    match factor {
+        SqueezeFactor::BorrowGrowth => weights.borrow_growth,
+        SqueezeFactor::BrRatio => weights.br_ratio,
+        SqueezeFactor::Funding => weights.funding,
+        SqueezeFactor::OpenInterest => weights.open_interest,
+        SqueezeFactor::LongShortRatio => weights.long_short_ratio,
+        SqueezeFactor::Inventory => weights.inventory,
+    }
+}
+
+// Empty if all the weights are zero
+fn weigh_factors(factors: Vec<(SqueezeFactor, Decimal)>, weights: &SqueezeWeights) -> Vec<SqueezeContribution> {
+    let total_weight = factors
+        .iter()
+        .map(|(factor, _)| factor_weight(weights, *factor))
+        .sum::<Decimal>();
+
+    if total_weight <= Decimal::ZERO {
+        return Vec::new();
+    }
+
+    factors
+        .into_iter()
+        .map(|(factor, value)| {
+            let points = value * factor_weight(weights, factor) / total_weight * Decimal::ONE_HUNDRED;
+            SqueezeContribution { factor, points }
+        })
+        .collect()
+}
+
+// The highest score the asset can get with any futures data. Margin data is known before
+// the futures and spot data are requested, so low scoring reports are dropped early.
+pub fn max_squeeze_score(margin_data: &MarginDataReport, weights: &SqueezeWeights) -> Decimal {
+    let inputs = SqueezeInputs {
+        margin_data,
+        funding_rate: None,
+        open_interest: &[],
+        long_short_ratio: &[],
+    };
+
+    let factors = inputs
+        .factors()
+        .into_iter()
+        .map(|(factor, value)| match factor {
+            SqueezeFactor::Funding | SqueezeFactor::OpenInterest | SqueezeFactor::LongShortRatio => {
+                (factor, Decimal::ONE)
+            }
+            _ => (factor, value),
+        })
+        .collect();
+
+    weigh_factors(factors, weights)
+        .iter()
+        .map(|item| item.points)
+        .sum::<Decimal>()
+        .round()
+}
+
+pub fn calculate_squeeze_score(inputs: SqueezeInputs, weights: &SqueezeWeights) -> SqueezeScore {
+    let mut contributions = weigh_factors(inputs.factors(), weights);
+    let score = contributions.iter().map(|item| item.points).sum::<Decimal>();
+
+    contributions.retain(|item| item.points > Decimal::ZERO);
+    contributions.sort_by_key(|item| Reverse(item.points));
+    contributions.truncate(MAIN_FACTORS_NUMBER);
+
+    let main_factors = contributions
+        .into_iter()
+        .map(|item| SqueezeContribution {
+            factor: item.factor,
+            points: item.points.round(),
+        })
+        .collect();
+
+    SqueezeScore {
+        score: score.round(),
+        main_factors,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::structs::TimeDifference;
+
+    use super::*;
+
+    fn margin_data_report() -> MarginDataReport {
+        MarginDataReport {
+            total_borrow: Decimal::from(300),
+            total_borrow_usdt: Decimal::from(300_000),
+            total_repay: Decimal::from(30),
+            total_repay_usdt: Decimal::from(30_000),
+            borrow_change: Decimal::from(50),
+            borrow_z_score: None,
+            repay_change: Decimal::ZERO,
+            br_ratio: Decimal::TEN,
+            available: Decimal::from(200),
+            max_available: Some(Decimal::from(500)),
+            interest_rate: None,
+            isolated: Vec::new(),
+            isolated_only: false,
+        }
+    }
+
+    #[test]
+    fn test_calculate_squeeze_score() {
+        let margin_data = margin_data_report();
+
+        let funding_rate = FundingRateReport {
+            funding_rate: Decimal::new(-5, 4),
+            next_funding_time: TimeDifference::calculate(30),
+            history: Vec::new(),
+            cumulative_24h: Decimal::ZERO,
+            annualized: Decimal::ZERO,
+        };
+
+        let open_interest = vec![
+            OpenInterestChange { interval: Interval::M5, change: Decimal::ONE },
+            OpenInterestChange { interval: Interval::H1, change: Decimal::from(5) },
+        ];
+
+        let long_short_ratio = vec![
+            LongShortRatioReport { interval: Interval::Now, ratio: Decimal::ONE },
+        ];
+
+        let inputs = SqueezeInputs {
+            margin_data: &margin_data,
+            funding_rate: Some(&funding_rate),
+            open_interest: &open_interest,
+            long_short_ratio: &long_short_ratio,
+        };
+
+        // borrow growth 0.5 * 3, B/R ratio 1 * 2, funding 0.5 * 2,
+        // OI 0.5 * 1, long short ratio 0.5 * 1, inventory 0.6 * 1
+        let result = calculate_squeeze_score(inputs, &SqueezeWeights::default());
+
+        let expected = SqueezeScore {
+            score: Decimal::from(61),
+            main_factors: vec![
+                SqueezeContribution { factor: SqueezeFactor::BrRatio, points: Decimal::from(20) },
+                SqueezeContribution { factor: SqueezeFactor::BorrowGrowth, points: Decimal::from(15) },
+                SqueezeContribution { factor: SqueezeFactor::Funding, points: Decimal::from(10) },
+            ],
+        };
+
+        assert_eq!(result, expected);
+
+        let inputs = SqueezeInputs {
+            margin_data: &margin_data,
+            funding_rate: None,
+            open_interest: &[],
+            long_short_ratio: &[],
+        };
+
+        // borrow growth 0.5 * 3, B/R ratio 1 * 2, inventory 0.6 * 1
+        let result = calculate_squeeze_score(inputs, &SqueezeWeights::default());
+        assert_eq!(result.score, Decimal::from(41));
+
+        // funding, OI and long short ratio are taken at their max, 41 + 20 + 10 + 10 points
+        let result = max_squeeze_score(&margin_data, &SqueezeWeights::default());
+        assert_eq!(result, Decimal::from(81));
+
+        let weights = SqueezeWeights {
+            borrow_growth: Decimal::ZERO,
+            br_ratio: Decimal::ZERO,
+            funding: Decimal::ZERO,
+            open_interest: Decimal::ZERO,
+            long_short_ratio: Decimal::ZERO,
+            inventory: Decimal::ZERO,
+        };
+        assert_eq!(max_squeeze_score(&margin_data, &weights), Decimal::ZERO);
+
+        // borrow growth 0.5 * 3, B/R ratio 1 * 2 out of 9 without inventory
+        let margin_data = MarginDataReport {
+            available: Decimal::ZERO,
+            max_available: None,
+            isolated_only: true,
+            ..margin_data_report()
+        };
+        let inputs = SqueezeInputs {
+            margin_data: &margin_data,
+            funding_rate: None,
+            open_interest: &[],
+            long_short_ratio: &[],
+        };
+        let result = calculate_squeeze_score(inputs, &SqueezeWeights::default());
+        assert_eq!(result.score, Decimal::from(39));
+    }
+}
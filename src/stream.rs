@@ -0,0 +1,659 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use log::{error, info, warn};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use tokio::sync::RwLock;
+use tokio::task::{self, JoinHandle};
+use tokio::time::interval;
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::binance::{to_datetime_utc, BinanceCandle, BinanceDailyVolume, BinanceFundingRate};
+use crate::error::Result;
+use crate::margin_data::MarginDataProcessor;
+use crate::report::MARKET_SYMBOLS;
+use crate::symbols::SymbolRegistry;
+
+const SPOT_STREAM_URL: &str = "wss://stream.binance.com:9443/stream?streams=";
+const FUTURES_STREAM_URL: &str = "wss://fstream.binance.com/stream?streams=";
+
+const SPOT_TICKERS_STREAM: &str = "!miniTicker@arr";
+const MARK_PRICES_STREAM: &str = "!markPrice@arr@1s";
+// The latest liquidation of every futures symbol within 1 second
+const LIQUIDATIONS_STREAM: &str = "!forceOrder@arr";
+const KLINE_STREAM_SUFFIX: &str = "@kline_5m";
+const CANDLE_INTERVAL_MINUTES: i64 = 5;
+// Futures connections are limited to 200 streams
+const KLINE_STREAMS_PER_CONNECTION: usize = 200;
+
+// Same number of 5-minute candles as requested over REST
+const CANDLES_WINDOW: usize = 50;
+// Data which hasn't been updated for this period is requested over REST instead
+const STALE_AFTER_SECS: i64 = 60;
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+const SUBSCRIPTIONS_UPDATE_INTERVAL: Duration = Duration::from_secs(300);
+// Liquidations are kept for the longest report interval
+const LIQUIDATIONS_WINDOW_HOURS: i64 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Market {
+    Spot,
+    Futures,
+}
+
+impl Market {
+    fn url(&self) -> &'static str {
+        match self {
+            Market::Spot => SPOT_STREAM_URL,
+            Market::Futures => FUTURES_STREAM_URL,
+        }
+    }
+}
+
+// Payload of a combined stream, the data format depends on the stream name
+#[derive(Debug, Deserialize)]
+struct StreamMessage {
+    stream: String,
+    data: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct KlineEvent {
+    #[serde(rename = "s")]
+    symbol: String,
+    #[serde(rename = "k")]
+    kline: Kline,
+}
+
+#[derive(Debug, Deserialize)]
+struct Kline {
+    #[serde(rename = "t", deserialize_with = "to_datetime_utc")]
+    open_time: DateTime<Utc>,
+    #[serde(rename = "T", deserialize_with = "to_datetime_utc")]
+    close_time: DateTime<Utc>,
+    #[serde(rename = "o")]
+    open: Decimal,
+    #[serde(rename = "h")]
+    high: Decimal,
+    #[serde(rename = "l")]
+    low: Decimal,
+    #[serde(rename = "c")]
+    close: Decimal,
+    #[serde(rename = "v")]
+    volume: Decimal,
+    #[serde(rename = "x")]
+    is_closed: bool,
+    #[serde(rename = "q")]
+    quote_volume: Decimal,
+    #[serde(rename = "V")]
+    buy_base_volume: Decimal,
+    #[serde(rename = "Q")]
+    buy_quote_volume: Decimal,
+}
+
+impl From<Kline> for BinanceCandle {
+    fn from(value: Kline) -> Self {
+        BinanceCandle {
+            open_time: value.open_time,
+            open: value.open,
+            high: value.high,
+            low: value.low,
+            close: value.close,
+            volume: value.volume,
+            close_time: value.close_time,
+            is_closed: value.is_closed,
+            quote_volume: value.quote_volume,
+            buy_base_volume: value.buy_base_volume,
+            sell_quote_volume: value.quote_volume - value.buy_quote_volume,
+            buy_quote_volume: value.buy_quote_volume,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct MarkPriceEvent {
+    #[serde(rename = "s")]
+    symbol: String,
+    #[serde(rename = "p")]
+    mark_price: Decimal,
+    #[serde(rename = "i")]
+    index_price: Decimal,
+    #[serde(rename = "r")]
+    funding_rate: Decimal,
+    #[serde(rename = "T", deserialize_with = "to_datetime_utc")]
+    next_funding_time: DateTime<Utc>,
+}
+
+impl From<MarkPriceEvent> for BinanceFundingRate {
+    fn from(value: MarkPriceEvent) -> Self {
+        BinanceFundingRate {
+            mark_price: value.mark_price,
+            index_price: value.index_price,
+            last_funding_rate: value.funding_rate,
+            next_funding_time: value.next_funding_time,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct MiniTickerEvent {
+    #[serde(rename = "s")]
+    symbol: String,
+    #[serde(rename = "c")]
+    last_price: Decimal,
+    #[serde(rename = "v")]
+    volume: Decimal,
+    #[serde(rename = "q")]
+    quote_volume: Decimal,
+}
+
+impl From<MiniTickerEvent> for BinanceDailyVolume {
+    fn from(value: MiniTickerEvent) -> Self {
+        BinanceDailyVolume {
+            symbol: value.symbol,
+            last_price: value.last_price,
+            volume: value.volume,
+            quote_volume: value.quote_volume,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+enum OrderSide {
+    Buy,
+    Sell,
+}
+
+#[derive(Debug, Deserialize)]
+struct ForceOrderEvent {
+    #[serde(rename = "o")]
+    order: ForceOrder,
+}
+
+#[derive(Debug, Deserialize)]
+struct ForceOrder {
+    #[serde(rename = "s")]
+    symbol: String,
+    #[serde(rename = "S")]
+    side: OrderSide,
+    #[serde(rename = "ap")]
+    average_price: Decimal,
+    // Accumulated filled quantity
+    #[serde(rename = "z")]
+    filled: Decimal,
+    #[serde(rename = "T", deserialize_with = "to_datetime_utc")]
+    time: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LiquidationSide {
+    Long,
+    Short,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Liquidation {
+    pub time: DateTime<Utc>,
+    pub side: LiquidationSide,
+    pub usd: Decimal,
+}
+
+impl From<ForceOrder> for Liquidation {
+    // Liquidated longs are closed with sell orders
+    fn from(value: ForceOrder) -> Self {
+        let side = match value.side {
+            OrderSide::Sell => LiquidationSide::Long,
+            OrderSide::Buy => LiquidationSide::Short,
+        };
+
+        Liquidation {
+            time: value.time,
+            side,
+            usd: value.average_price * value.filled,
+        }
+    }
+}
+
+// Liquidations of a symbol since the moment the stream was connected
+#[derive(Debug, Clone)]
+pub struct LiquidationsFeed {
+    pub since: DateTime<Utc>,
+    pub liquidations: Vec<Liquidation>,
+}
+
+#[derive(Debug)]
+struct Timed<T> {
+    value: T,
+    received_at: DateTime<Utc>,
+}
+
+impl<T> Timed<T> {
+    fn new(value: T, received_at: DateTime<Utc>) -> Self {
+        Self { value, received_at }
+    }
+
+    fn fresh(&self, now: DateTime<Utc>) -> Option<&T> {
+        let is_fresh = (now - self.received_at).num_seconds() <= STALE_AFTER_SECS;
+        is_fresh.then_some(&self.value)
+    }
+}
+
+type CandlesWindow = VecDeque<BinanceCandle>;
+
+// Replaces the candle with the same open time or inserts it keeping the window sorted
+// from the oldest to the newest candle. The oldest candles are dropped above the limit.
+fn upsert_candle(window: &mut CandlesWindow, candle: BinanceCandle) {
+    match window.binary_search_by_key(&candle.open_time, |item| item.open_time) {
+        Ok(idx) => window[idx] = candle,
+        Err(idx) => window.insert(idx, candle),
+    }
+
+    while window.len() > CANDLES_WINDOW {
+        window.pop_front();
+    }
+}
+
+// Window has every 5-minute period up to the current one. Periods are missed
+// while the stream is reconnecting, and such a window covers more time than it seems.
+fn is_continuous(window: &CandlesWindow, now: DateTime<Utc>) -> bool {
+    let step = chrono::Duration::minutes(CANDLE_INTERVAL_MINUTES);
+
+    let Some(newest) = window.back() else {
+        return false;
+    };
+
+    if now - newest.open_time >= step {
+        return false;
+    }
+
+    window.iter().zip(window.iter().skip(1)).all(|(a, b)| b.open_time - a.open_time == step)
+}
+
+#[derive(Debug, Default)]
+struct MarketData {
+    candles: HashMap<(Market, String), Timed<CandlesWindow>>,
+    mark_prices: HashMap<String, Timed<BinanceFundingRate>>,
+    tickers: HashMap<String, Timed<BinanceDailyVolume>>,
+    liquidations: HashMap<String, VecDeque<Liquidation>>,
+    // When the liquidations stream was connected, None while it is disconnected
+    liquidations_since: Option<DateTime<Utc>>,
+}
+
+// In-memory market data kept up to date by Binance WebSocket streams.
+// Getters return None when the data is missing or stale, so callers can use REST instead.
+#[derive(Debug, Default)]
+pub struct MarketStream {
+    data: RwLock<MarketData>,
+}
+
+impl MarketStream {
+    // Full window of candles, newest last, or None until the window is filled without gaps
+    pub async fn candles(&self, market: Market, symbol: &str) -> Option<Vec<BinanceCandle>> {
+        self.candles_at(market, symbol, Utc::now()).await
+    }
+
+    async fn candles_at(&self, market: Market, symbol: &str, now: DateTime<Utc>) -> Option<Vec<BinanceCandle>> {
+        let lock = self.data.read().await;
+        let window = lock.candles.get(&(market, symbol.to_string()))?.fresh(now)?;
+
+        if window.len() < CANDLES_WINDOW || !is_continuous(window, now) {
+            return None;
+        }
+
+        let candles = window
+            .iter()
+            .map(|candle| BinanceCandle {
+                is_closed: candle.is_closed || candle.close_time <= now,
+                ..candle.clone()
+            })
+            .collect();
+
+        Some(candles)
+    }
+
+    // Fills the window with candles received over REST, which also fills the gaps
+    // left by reconnects. The stream keeps the window updated afterwards.
+    pub async fn seed_candles(&self, market: Market, symbol: &str, candles: &[BinanceCandle]) {
+        let now = Utc::now();
+        let mut lock = self.data.write().await;
+        let entry = lock
+            .candles
+            .entry((market, symbol.to_string()))
+            .or_insert_with(|| Timed::new(VecDeque::new(), now));
+
+        for candle in candles {
+            upsert_candle(&mut entry.value, candle.clone());
+        }
+        entry.received_at = now;
+    }
+
+    pub async fn mark_price(&self, symbol: &str) -> Option<BinanceFundingRate> {
+        let lock = self.data.read().await;
+        lock.mark_prices.get(symbol)?.fresh(Utc::now()).cloned()
+    }
+
+    pub async fn ticker(&self, symbol: &str) -> Option<BinanceDailyVolume> {
+        let lock = self.data.read().await;
+        lock.tickers.get(symbol)?.fresh(Utc::now()).cloned()
+    }
+
+    // None while the liquidations stream is disconnected, because missing liquidations can't be told from zero
+    pub async fn liquidations(&self, symbol: &str) -> Option<LiquidationsFeed> {
+        let lock = self.data.read().await;
+        let since = lock.liquidations_since?;
+
+        let liquidations = lock
+            .liquidations
+            .get(symbol)
+            .map(|items| items.iter().cloned().collect())
+            .unwrap_or_default();
+
+        Some(LiquidationsFeed { since, liquidations })
+    }
+
+    async fn set_connected(&self, streams: &[String], connected: bool) {
+        if !streams.iter().any(|item| item == LIQUIDATIONS_STREAM) {
+            return;
+        }
+
+        let mut lock = self.data.write().await;
+        lock.liquidations_since = connected.then(Utc::now);
+    }
+
+    async fn apply(&self, market: Market, message: StreamMessage) -> Result<()> {
+        let now = Utc::now();
+
+        if message.stream.ends_with(KLINE_STREAM_SUFFIX) {
+            let event = serde_json::from_value::<KlineEvent>(message.data)?;
+
+            let mut lock = self.data.write().await;
+            let entry = lock
+                .candles
+                .entry((market, event.symbol))
+                .or_insert_with(|| Timed::new(VecDeque::new(), now));
+
+            upsert_candle(&mut entry.value, event.kline.into());
+            entry.received_at = now;
+        } else if message.stream == MARK_PRICES_STREAM {
+            let events = serde_json::from_value::<Vec<MarkPriceEvent>>(message.data)?;
+
+            let mut lock = self.data.write().await;
+            for event in events {
+                lock.mark_prices.insert(event.symbol.clone(), Timed::new(event.into(), now));
+            }
+        } else if message.stream == SPOT_TICKERS_STREAM {
+            let events = serde_json::from_value::<Vec<MiniTickerEvent>>(message.data)?;
+
+            let mut lock = self.data.write().await;
+            for event in events {
+                lock.tickers.insert(event.symbol.clone(), Timed::new(event.into(), now));
+            }
+        } else if message.stream == LIQUIDATIONS_STREAM {
+            let event = serde_json::from_value::<ForceOrderEvent>(message.data)?;
+            let window_start = now - chrono::Duration::hours(LIQUIDATIONS_WINDOW_HOURS);
+
+            let mut lock = self.data.write().await;
+            let liquidations = lock.liquidations.entry(event.order.symbol.clone()).or_default();
+            liquidations.push_back(event.order.into());
+
+            while liquidations.front().is_some_and(|item| item.time < window_start) {
+                liquidations.pop_front();
+            }
+        }
+
+        Ok(())
+    }
+
+    // Drops the windows of the symbols which are not subscribed anymore
+    async fn retain_candles(&self, market: Market, streams: &[String]) {
+        let mut lock = self.data.write().await;
+        lock.candles.retain(|(item_market, symbol), _| {
+            *item_market != market || streams.contains(&kline_stream(symbol))
+        });
+    }
+}
+
+fn kline_stream(symbol: &str) -> String {
+    format!("{}{}", symbol.to_lowercase(), KLINE_STREAM_SUFFIX)
+}
+
+async fn read_connection(market: Market, streams: &[String], stream: &MarketStream) -> Result<()> {
+    let url = format!("{}{}", market.url(), streams.join("/"));
+    let (mut socket, _) = connect_async(url).await?;
+    stream.set_connected(streams, true).await;
+
+    while let Some(message) = socket.next().await {
+        // Pings are answered by tungstenite on the next read
+        let Message::Text(text) = message? else {
+            continue;
+        };
+
+        let message = serde_json::from_str::<StreamMessage>(&text)?;
+        if let Err(e) = stream.apply(market, message).await {
+            warn!("Failed to parse {:?} stream message: {}", market, e);
+        }
+    }
+
+    Ok(())
+}
+
+// Keeps the connection alive forever. Binance closes every connection after 24 hours.
+async fn run_connection(market: Market, streams: Vec<String>, stream: Arc<MarketStream>) {
+    loop {
+        info!("Connecting to {} {:?} streams", streams.len(), market);
+
+        match read_connection(market, &streams, &stream).await {
+            Ok(_) => warn!("{:?} stream connection closed", market),
+            Err(e) => error!("{:?} stream connection failed: {}", market, e),
+        }
+        stream.set_connected(&streams, false).await;
+
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
+
+// Kline streams of the spot pairs and perpetual contracts of tracked margin assets
+async fn kline_streams(
+    symbols: &SymbolRegistry,
+    margin_processor: &MarginDataProcessor,
+) -> HashMap<Market, Vec<String>> {
+    let mut spot = Vec::new();
+    let mut futures = Vec::new();
+
+    // Majors are not margin assets, but every report shows them
+    for (_, symbol) in MARKET_SYMBOLS {
+        spot.push(kline_stream(symbol));
+    }
+
+    for asset in margin_processor.snapshot().await.into_keys() {
+        let pair = symbols.spot_pair(&asset).await;
+        if symbols.spot_symbol(&pair.symbol).await.is_some() {
+            spot.push(kline_stream(&pair.symbol));
+        }
+
+        if let Some(contract) = symbols.futures_contract(&asset).await {
+            futures.push(kline_stream(&contract.info.symbol));
+        }
+    }
+
+    spot.sort();
+    spot.dedup();
+    futures.sort();
+    futures.dedup();
+
+    HashMap::from([(Market::Spot, spot), (Market::Futures, futures)])
+}
+
+// Ticker and mark price streams cover all symbols at once. Kline streams are
+// resubscribed when the set of tracked assets changes.
+pub async fn run_market_streams(
+    stream: Arc<MarketStream>,
+    symbols: Arc<SymbolRegistry>,
+    margin_processor: Arc<MarginDataProcessor>,
+) {
+    task::spawn(run_connection(Market::Spot, vec![SPOT_TICKERS_STREAM.to_string()], stream.clone()));
+    task::spawn(run_connection(Market::Futures, vec![MARK_PRICES_STREAM.to_string()], stream.clone()));
+    task::spawn(run_connection(Market::Futures, vec![LIQUIDATIONS_STREAM.to_string()], stream.clone()));
+
+    let mut subscriptions = HashMap::new();
+    let mut connections: Vec<JoinHandle<()>> = Vec::new();
+    let mut interval = interval(SUBSCRIPTIONS_UPDATE_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        let streams = kline_streams(&symbols, &margin_processor).await;
+        if streams == subscriptions {
+            continue;
+        }
+
+        connections.drain(..).for_each(|connection| connection.abort());
+
+        for (market, market_streams) in &streams {
+            stream.retain_candles(*market, market_streams).await;
+
+            for chunk in market_streams.chunks(KLINE_STREAMS_PER_CONNECTION) {
+                let connection = run_connection(*market, chunk.to_vec(), stream.clone());
+                connections.push(task::spawn(connection));
+            }
+        }
+
+        subscriptions = streams;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::DurationRound;
+
+    use crate::utils::candles_fixture;
+
+    use super::*;
+
+    #[test]
+    fn test_upsert_candle() {
+        let candles = candles_fixture();
+        let newest = candles.iter().max_by_key(|candle| candle.open_time).unwrap().clone();
+        let mut window = VecDeque::new();
+
+        for candle in candles.iter() {
+            upsert_candle(&mut window, candle.clone());
+        }
+        assert_eq!(window.len(), candles.len());
+        assert_eq!(window.back().unwrap().open_time, newest.open_time);
+
+        let updated = BinanceCandle {
+            close: Decimal::ONE,
+            ..newest.clone()
+        };
+        upsert_candle(&mut window, updated);
+        assert_eq!(window.len(), candles.len());
+        assert_eq!(window.back().unwrap().close, Decimal::ONE);
+
+        let oldest = window.front().unwrap().open_time;
+        for i in 1..=CANDLES_WINDOW as i64 {
+            let newer = BinanceCandle {
+                open_time: newest.open_time + chrono::Duration::minutes(5 * i),
+                ..newest.clone()
+            };
+            upsert_candle(&mut window, newer);
+        }
+        assert_eq!(window.len(), CANDLES_WINDOW);
+        assert!(window.front().unwrap().open_time > oldest);
+        assert!(window.iter().zip(window.iter().skip(1)).all(|(a, b)| a.open_time < b.open_time));
+    }
+
+    #[tokio::test]
+    async fn test_candles_with_gap() {
+        let now = Utc::now();
+        let current_period = now.duration_trunc(chrono::Duration::minutes(CANDLE_INTERVAL_MINUTES)).unwrap();
+        let template = candles_fixture().remove(0);
+
+        let candle = |periods_ago: i64| BinanceCandle {
+            open_time: current_period - chrono::Duration::minutes(CANDLE_INTERVAL_MINUTES * periods_ago),
+            ..template.clone()
+        };
+
+        let stream = MarketStream::default();
+        let candles = (0..CANDLES_WINDOW as i64).map(candle).collect::<Vec<_>>();
+        stream.seed_candles(Market::Spot, "SOLUSDT", &candles).await;
+
+        let result = stream.candles_at(Market::Spot, "SOLUSDT", now).await.unwrap();
+        assert_eq!(result.len(), CANDLES_WINDOW);
+
+        // current period hasn't arrived yet
+        let later = current_period + chrono::Duration::minutes(CANDLE_INTERVAL_MINUTES);
+        assert!(stream.candles_at(Market::Spot, "SOLUSDT", later).await.is_none());
+
+        // a full window with a period missed during a reconnect
+        let stream = MarketStream::default();
+        let candles = (0..=CANDLES_WINDOW as i64).filter(|i| *i != 10).map(candle).collect::<Vec<_>>();
+        stream.seed_candles(Market::Spot, "SOLUSDT", &candles).await;
+        assert!(stream.candles_at(Market::Spot, "SOLUSDT", now).await.is_none());
+
+        // REST candles fill the gap
+        stream.seed_candles(Market::Spot, "SOLUSDT", &[candle(10)]).await;
+        assert!(stream.candles_at(Market::Spot, "SOLUSDT", now).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_apply_stream_messages() {
+        let stream = MarketStream::default();
+
+        let kline = r#"{"stream": "solusdt@kline_5m", "data": {"e": "kline", "E": 1760694300000, "s": "SOLUSDT",
+            "k": {"t": 1760694000000, "T": 1760694299999, "s": "SOLUSDT", "i": "5m", "o": "180.10", "c": "181.00",
+            "h": "181.20", "l": "180.00", "v": "1000", "n": 120, "x": false, "q": "180500", "V": "600", "Q": "108300"}}}"#;
+        let message = serde_json::from_str(kline).unwrap();
+        stream.apply(Market::Spot, message).await.unwrap();
+
+        // The window is not filled yet
+        assert!(stream.candles(Market::Spot, "SOLUSDT").await.is_none());
+
+        let lock = stream.data.read().await;
+        let window = &lock.candles[&(Market::Spot, "SOLUSDT".to_string())].value;
+        assert_eq!(window[0].sell_quote_volume, Decimal::from(72_200));
+        drop(lock);
+
+        let mark_price = r#"{"stream": "!markPrice@arr@1s", "data": [{"e": "markPriceUpdate", "E": 1760694300000,
+            "s": "SOLUSDT", "p": "181.05", "i": "181.00", "P": "181.02", "r": "-0.00010000", "T": 1760716800000}]}"#;
+        let message = serde_json::from_str(mark_price).unwrap();
+        stream.apply(Market::Futures, message).await.unwrap();
+
+        let result = stream.mark_price("SOLUSDT").await.unwrap();
+        assert_eq!(result.last_funding_rate, Decimal::new(-1, 4));
+        assert_eq!(result.mark_price, Decimal::new(18105, 2));
+
+        let ticker = r#"{"stream": "!miniTicker@arr", "data": [{"e": "24hrMiniTicker", "E": 1760694300000,
+            "s": "SOLUSDT", "c": "181.00", "o": "175.00", "h": "183.00", "l": "174.00", "v": "500000", "q": "90000000"}]}"#;
+        let message = serde_json::from_str(ticker).unwrap();
+        stream.apply(Market::Spot, message).await.unwrap();
+
+        let result = stream.ticker("SOLUSDT").await.unwrap();
+        assert_eq!(result.quote_volume, Decimal::from(90_000_000));
+        assert!(stream.ticker("BTCUSDT").await.is_none());
+
+        let streams = vec![LIQUIDATIONS_STREAM.to_string()];
+        stream.set_connected(&streams, true).await;
+        let time = Utc::now().timestamp_millis();
+
+        let force_order = format!(r#"{{"stream": "!forceOrder@arr", "data": {{"e": "forceOrder", "E": {time},
+            "o": {{"s": "SOLUSDT", "S": "SELL", "o": "LIMIT", "f": "IOC", "q": "10", "p": "179.00",
+            "ap": "180.00", "X": "FILLED", "l": "10", "z": "10", "T": {time}}}}}}}"#);
+        let message = serde_json::from_str(&force_order).unwrap();
+        stream.apply(Market::Futures, message).await.unwrap();
+
+        let result = stream.liquidations("SOLUSDT").await.unwrap();
+        assert_eq!(result.liquidations.len(), 1);
+        assert_eq!(result.liquidations[0].side, LiquidationSide::Long);
+        assert_eq!(result.liquidations[0].usd, Decimal::from(1800));
+
+        stream.set_connected(&streams, false).await;
+        assert!(stream.liquidations("SOLUSDT").await.is_none());
+    }
+}
@@ -1,13 +1,24 @@
+use chrono::{DateTime, Duration, Utc};
 use numfmt::Numeric;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 
+use crate::config::{AnomalyConfig, InterestRateConfig, RepaySurgeConfig};
+use crate::digest::Digest;
+use crate::leverage::LeverageSummary;
+use crate::symbols::FuturesContract;
 use crate::utils::find_percentage_diff;
 
 pub enum MarginDataMessage {
     Error(String),
     Update(MarginDataUpdated),
     New(MarginData),
+    Removed(MarginData),
+    Digest(Digest),
+    InventoryDepletion(InventoryDepletion),
+    FuturesListing(FuturesListing),
+    IsolatedUpdate(IsolatedMarginDataUpdated),
+    LeverageSummary(LeverageSummary),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
@@ -66,6 +77,9 @@ pub struct MarginData {
     pub total_borrow_in_usdt: Decimal,
     pub total_repay_in_usdt: Decimal,
     pub available: Decimal,
+    // Missing if binance did not return interest rates
+    #[serde(default)]
+    pub hourly_interest_rate: Option<Decimal>,
 }
 
 #[cfg(test)]
@@ -78,6 +92,100 @@ impl Default for MarginData {
             total_borrow_in_usdt: Decimal::ONE_THOUSAND,
             total_repay_in_usdt: Decimal::ONE_HUNDRED,
             available: Decimal::TEN,
+            hourly_interest_rate: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct IsolatedBorrow {
+    pub total_borrow: Decimal,
+    pub total_repay: Decimal,
+    pub total_borrow_in_usdt: Decimal,
+    pub total_repay_in_usdt: Decimal,
+}
+
+// Borrowings of an isolated margin pair, both assets can be borrowed
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct IsolatedMarginData {
+    pub symbol: String,
+    pub base_asset: String,
+    pub quote_asset: String,
+    pub base: IsolatedBorrow,
+    pub quote: IsolatedBorrow,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct InterestRatePoint {
+    pub time: DateTime<Utc>,
+    pub hourly_rate: Decimal,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignalKind {
+    Borrow,
+    RepaySurge,
+    InterestRateJump,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InventoryDepletionKind {
+    BelowRecentMax,
+    SharpDrop,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InventoryDepletion {
+    pub kind: InventoryDepletionKind,
+    pub data: MarginData,
+    pub previous: Decimal,
+    pub recent_max: Decimal,
+}
+
+impl InventoryDepletion {
+    pub fn change_from_previous(&self) -> Decimal {
+        find_percentage_diff(self.data.available, self.previous)
+    }
+
+    pub fn change_from_recent_max(&self) -> Decimal {
+        find_percentage_diff(self.data.available, self.recent_max)
+    }
+}
+
+// New perpetual contract of a tracked margin asset
+#[derive(Debug, Clone)]
+pub struct FuturesListing {
+    pub contract: FuturesContract,
+    pub data: MarginData,
+}
+
+#[derive(Debug, Clone)]
+pub struct IsolatedMarginDataUpdated {
+    pub old: IsolatedMarginData,
+    pub new: IsolatedMarginData,
+    // Base borrow change in standard deviations of the pair
+    pub borrow_z_score: Option<f64>,
+}
+
+impl IsolatedMarginDataUpdated {
+    // Base asset borrowings in the shape of cross margin data, so the same signals apply.
+    // Isolated pairs have no public inventory, thus it is left empty.
+    pub fn base_update(&self) -> MarginDataUpdated {
+        let to_margin_data = |data: &IsolatedMarginData| MarginData {
+            asset: data.base_asset.clone(),
+            total_borrow: data.base.total_borrow,
+            total_repay: data.base.total_repay,
+            total_borrow_in_usdt: data.base.total_borrow_in_usdt,
+            total_repay_in_usdt: data.base.total_repay_in_usdt,
+            available: Decimal::ZERO,
+            hourly_interest_rate: None,
+        };
+
+        MarginDataUpdated {
+            old: to_margin_data(&self.old),
+            new: to_margin_data(&self.new),
+            borrow_z_score: self.borrow_z_score,
+            max_available: None,
         }
     }
 }
@@ -86,13 +194,13 @@ impl Default for MarginData {
 pub struct MarginDataUpdated {
     pub old: MarginData,
     pub new: MarginData,
+    // Borrow change in standard deviations of the asset, missing until the statistics warm up
+    pub borrow_z_score: Option<f64>,
+    // The highest available inventory over the inventory window, the latest poll included
+    pub max_available: Option<Decimal>,
 }
 
 impl MarginDataUpdated {
-    pub fn is_more_than_1m(&self) -> bool {
-        self.new.total_borrow_in_usdt >= Decimal::from(1_000_000)
-    }
-
     pub fn borrow_change(&self) -> Decimal {
         find_percentage_diff(self.new.total_borrow, self.old.total_borrow)
     }
@@ -114,6 +222,239 @@ impl MarginDataUpdated {
     }
 
     pub fn is_borrow_big_enough(&self) -> bool {
-        self.new.total_borrow / self.new.total_repay > Decimal::from(5)
+        self.new.total_borrow > self.new.total_repay * Decimal::from(5)
+    }
+
+    // Borrow growth stands out of the usual changes of the asset.
+    // Fixed thresholds are used while its statistics are warming up.
+    pub fn is_borrow_anomaly(&self, config: &AnomalyConfig) -> bool {
+        let is_big_enough = self.new.total_borrow_in_usdt >= config.min_borrow_usdt;
+
+        match self.borrow_z_score {
+            Some(z_score) => {
+                is_big_enough && z_score >= config.z_score && self.borrow_change().is_sign_positive()
+            }
+            None => self.is_borrowing_rapidly_increased() || (is_big_enough && self.is_percent_changed_enough()),
+        }
+    }
+
+    pub fn br_ratio_change(&self) -> Option<Decimal> {
+        let old = self.old.total_borrow.checked_div(self.old.total_repay)?;
+        let new = self.new.total_borrow.checked_div(self.new.total_repay)?;
+        Some(find_percentage_diff(new, old))
+    }
+
+    // Repayments jumped and B/R ratio collapsed, i.e. shorts are being closed
+    pub fn is_repay_surge(&self, config: &RepaySurgeConfig) -> bool {
+        let Some(br_ratio_change) = self.br_ratio_change() else {
+            return false;
+        };
+
+        self.new.total_repay_in_usdt >= config.min_repay_usdt
+            && self.repay_change() >= config.repay_change_percent
+            && br_ratio_change <= -config.br_ratio_drop_percent
+    }
+
+    pub fn interest_rate_change(&self) -> Option<Decimal> {
+        let old = self.old.hourly_interest_rate.filter(|rate| !rate.is_zero())?;
+        let new = self.new.hourly_interest_rate?;
+        Some(find_percentage_diff(new, old))
+    }
+
+    // Change of the current rate against the lowest one since the given time,
+    // the previous poll is included in case the history is empty
+    pub fn interest_rate_jump(&self, history: &[InterestRatePoint], since: DateTime<Utc>) -> Option<Decimal> {
+        let new = self.new.hourly_interest_rate?;
+        let lowest = history
+            .iter()
+            .filter(|point| point.time >= since)
+            .map(|point| point.hourly_rate)
+            .chain(self.old.hourly_interest_rate)
+            .filter(|rate| !rate.is_zero())
+            .min()?;
+
+        Some(find_percentage_diff(new, lowest))
+    }
+
+    // Rate is known and borrowings are big enough for its jumps to matter
+    pub fn is_interest_rate_tracked(&self, config: &InterestRateConfig) -> bool {
+        self.new.hourly_interest_rate.is_some() && self.new.total_borrow_in_usdt >= config.min_borrow_usdt
+    }
+
+    // Lenders raise the rate when the asset is in high demand for borrowing.
+    // Compared to the history, so a rate creeping up over several polls is caught as well.
+    pub fn is_interest_rate_jump(
+        &self,
+        history: &[InterestRatePoint],
+        now: DateTime<Utc>,
+        config: &InterestRateConfig,
+    ) -> bool {
+        let since = now - Duration::hours(config.history_hours);
+
+        self.interest_rate_jump(history, since)
+            .is_some_and(|change| change >= config.jump_percent)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_is_repay_surge() {
+        let config = RepaySurgeConfig::default();
+
+        let old = MarginData {
+            total_borrow: Decimal::from(1000),
+            total_repay: Decimal::from(100),
+            ..Default::default()
+        };
+
+        let new = MarginData {
+            total_borrow: Decimal::from(1000),
+            total_repay: Decimal::from(400),
+            total_repay_in_usdt: Decimal::from(2_000_000),
+            ..Default::default()
+        };
+
+        let update = MarginDataUpdated { old: old.clone(), new: new.clone(), borrow_z_score: None, max_available: None };
+        assert_eq!(update.br_ratio_change(), Some(Decimal::from(-75)));
+        assert!(update.is_repay_surge(&config));
+
+        let small = MarginData {
+            total_repay_in_usdt: Decimal::from(500_000),
+            ..new.clone()
+        };
+        let update = MarginDataUpdated { old: old.clone(), new: small, borrow_z_score: None, max_available: None };
+        assert!(!update.is_repay_surge(&config));
+
+        // repay grew together with borrow, so B/R ratio barely changed
+        let both_grew = MarginData {
+            total_borrow: Decimal::from(3000),
+            ..new.clone()
+        };
+        let update = MarginDataUpdated { old: old.clone(), new: both_grew, borrow_z_score: None, max_available: None };
+        assert!(!update.is_repay_surge(&config));
+
+        let no_repay = MarginData {
+            total_repay: Decimal::ZERO,
+            ..old
+        };
+        let update = MarginDataUpdated { old: no_repay, new, borrow_z_score: None, max_available: None };
+        assert!(!update.is_repay_surge(&config));
+    }
+
+    #[test]
+    fn test_is_interest_rate_jump() {
+        let config = InterestRateConfig::default();
+        let now = Utc::now();
+
+        let old = MarginData {
+            hourly_interest_rate: Some(Decimal::new(4, 6)),
+            ..Default::default()
+        };
+
+        let new = MarginData {
+            hourly_interest_rate: Some(Decimal::new(8, 6)),
+            ..Default::default()
+        };
+
+        let update = MarginDataUpdated { old: old.clone(), new: new.clone(), borrow_z_score: None, max_available: None };
+        assert_eq!(update.interest_rate_change(), Some(Decimal::ONE_HUNDRED));
+        assert!(update.is_interest_rate_jump(&[], now, &config));
+
+        let update = MarginDataUpdated { old: new.clone(), new: old.clone(), borrow_z_score: None, max_available: None };
+        assert!(!update.is_interest_rate_jump(&[], now, &config));
+
+        // rate was not received in the previous poll and there is no history
+        let unknown = MarginData { hourly_interest_rate: None, ..old.clone() };
+        let update = MarginDataUpdated { old: unknown, new: new.clone(), borrow_z_score: None, max_available: None };
+        assert_eq!(update.interest_rate_change(), None);
+        assert!(!update.is_interest_rate_jump(&[], now, &config));
+
+        // rate crept up over several polls, each step is below the threshold
+        let point = |hours_ago: i64, rate: i64| InterestRatePoint {
+            time: now - Duration::hours(hours_ago),
+            hourly_rate: Decimal::new(rate, 6),
+        };
+        let history = vec![point(0, 8), point(1, 7), point(2, 6), point(3, 5), point(4, 4)];
+        let previous = MarginData { hourly_interest_rate: Some(Decimal::new(7, 6)), ..old };
+        let update = MarginDataUpdated { old: previous, new, borrow_z_score: None, max_available: None };
+        assert!(update.is_interest_rate_jump(&history, now, &config));
+
+        // the low rate is out of the history period
+        let history = vec![point(0, 8), point(1, 7), point(30, 4)];
+        assert!(!update.is_interest_rate_jump(&history, now, &config));
+    }
+
+    #[test]
+    fn test_is_interest_rate_tracked() {
+        let config = InterestRateConfig::default();
+        let now = Utc::now();
+
+        // B/R ratio is only 2, the rate doubles
+        let old = MarginData {
+            total_borrow: Decimal::from(2000),
+            total_repay: Decimal::from(1000),
+            total_borrow_in_usdt: Decimal::from(2_000_000),
+            hourly_interest_rate: Some(Decimal::new(4, 6)),
+            ..Default::default()
+        };
+        let new = MarginData { hourly_interest_rate: Some(Decimal::new(8, 6)), ..old.clone() };
+
+        let update = MarginDataUpdated { old: old.clone(), new: new.clone(), borrow_z_score: None, max_available: None };
+        assert!(!update.is_borrow_big_enough());
+        assert!(update.is_interest_rate_tracked(&config));
+        assert!(update.is_interest_rate_jump(&[], now, &config));
+
+        let small = MarginData { total_borrow_in_usdt: Decimal::from(500_000), ..new.clone() };
+        let update = MarginDataUpdated { old: old.clone(), new: small, borrow_z_score: None, max_available: None };
+        assert!(!update.is_interest_rate_tracked(&config));
+
+        let unknown = MarginData { hourly_interest_rate: None, ..new };
+        let update = MarginDataUpdated { old, new: unknown, borrow_z_score: None, max_available: None };
+        assert!(!update.is_interest_rate_tracked(&config));
+    }
+
+    #[test]
+    fn test_is_borrow_anomaly() {
+        let config = AnomalyConfig::default();
+
+        let old = MarginData {
+            total_borrow: Decimal::from(1000),
+            ..Default::default()
+        };
+
+        // 20% growth of a small asset is below the fixed thresholds and the size floor
+        let new = MarginData {
+            total_borrow: Decimal::from(1200),
+            total_borrow_in_usdt: Decimal::from(120_000),
+            ..Default::default()
+        };
+
+        let update = MarginDataUpdated { old: old.clone(), new: new.clone(), borrow_z_score: None, max_available: None };
+        assert!(!update.is_borrow_anomaly(&config));
+
+        let update = MarginDataUpdated { borrow_z_score: Some(4.2), ..update };
+        assert!(!update.is_borrow_anomaly(&config));
+
+        let big = MarginData { total_borrow_in_usdt: Decimal::from(1_200_000), ..new };
+        let update = MarginDataUpdated { new: big, ..update };
+        assert!(update.is_borrow_anomaly(&config));
+
+        let update = MarginDataUpdated { borrow_z_score: Some(2.0), ..update };
+        assert!(!update.is_borrow_anomaly(&config));
+
+        // 1100% growth is enough without statistics, but usual for a choppy asset
+        let new = MarginData {
+            total_borrow: Decimal::from(12000),
+            ..Default::default()
+        };
+
+        let update = MarginDataUpdated { old, new, borrow_z_score: None, max_available: None };
+        assert!(update.is_borrow_anomaly(&config));
+
+        let update = MarginDataUpdated { borrow_z_score: Some(1.5), ..update };
+        assert!(!update.is_borrow_anomaly(&config));
     }
 }
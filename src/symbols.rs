@@ -0,0 +1,728 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use log::{error, info};
+use rust_decimal::Decimal;
+use tokio::sync::mpsc::Sender;
+use tokio::sync::RwLock;
+use tokio::time::{interval_at, Instant};
+
+use crate::binance::{
+    Binance,
+    BinanceCandle,
+    BinanceDailyVolume,
+    BinanceExchangeInfoResponse,
+    BinanceFundingRate,
+    BinanceOrderBook,
+    BinanceSpotExchangeInfoSymbol,
+    BinanceSymbolFilter,
+};
+use crate::config::FuturesAlias;
+use crate::margin_data::MarginDataProcessor;
+use crate::structs::{FuturesListing, MarginDataMessage};
+
+const EXCHANGE_INFO_UPDATE_INTERVAL: Duration = Duration::from_secs(750);
+// Delay between attempts to load the symbols on startup
+const WARM_UP_RETRY_INTERVAL: Duration = Duration::from_secs(10);
+// Attempts to load the symbols on startup, the missing parts are loaded by the periodic update after that
+const WARM_UP_RETRIES: u32 = 6;
+
+// Quote assets which are considered to be worth 1 USD
+const USD_QUOTES: [&str; 4] = ["USDT", "USDC", "FDUSD", "USD1"];
+// Other quote assets are converted to USD by their price against this one
+const DEFAULT_QUOTE: &str = "USDT";
+// Prefixes of contracts like 1000PEPEUSDT, where one contract unit is worth many base asset units.
+// Longer numeric prefixes go first so 1000000MOG isn't taken for 1000 of 000MOG.
+const CONTRACT_MULTIPLIERS: [(&str, i64); 5] = [
+    ("1000000", 1_000_000),
+    ("100000", 100_000),
+    ("10000", 10_000),
+    ("1000", 1_000),
+    ("1M", 1_000_000),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpotPair {
+    pub symbol: String,
+    pub base_asset: String,
+    pub quote_asset: String,
+    // Price of the quote asset in USD at the moment of the last exchange info update
+    pub usd_rate: Decimal,
+}
+
+impl SpotPair {
+    // Used for the assets without resolved pairs, e.g. before the first exchange info update
+    pub fn with_default_quote(asset: &str) -> Self {
+        Self {
+            symbol: format!("{}{}", asset, DEFAULT_QUOTE),
+            base_asset: asset.to_string(),
+            quote_asset: DEFAULT_QUOTE.to_string(),
+            usd_rate: Decimal::ONE,
+        }
+    }
+
+    pub fn is_usd_quoted(&self) -> bool {
+        USD_QUOTES.contains(&self.quote_asset.as_str())
+    }
+
+    fn to_usd(&self, value: Decimal) -> Decimal {
+        value * self.usd_rate
+    }
+
+    // Prices and quote volumes are converted to USD, base volumes stay as they are
+    pub fn convert_candles(&self, candles: Vec<BinanceCandle>) -> Vec<BinanceCandle> {
+        candles
+            .into_iter()
+            .map(|candle| BinanceCandle {
+                open: self.to_usd(candle.open),
+                high: self.to_usd(candle.high),
+                low: self.to_usd(candle.low),
+                close: self.to_usd(candle.close),
+                quote_volume: self.to_usd(candle.quote_volume),
+                sell_quote_volume: self.to_usd(candle.sell_quote_volume),
+                buy_quote_volume: self.to_usd(candle.buy_quote_volume),
+                ..candle
+            })
+            .collect()
+    }
+
+    pub fn convert_order_book(&self, book: BinanceOrderBook) -> BinanceOrderBook {
+        let convert = |levels: Vec<[Decimal; 2]>| {
+            levels
+                .into_iter()
+                .map(|[price, quantity]| [self.to_usd(price), quantity])
+                .collect()
+        };
+
+        BinanceOrderBook {
+            bids: convert(book.bids),
+            asks: convert(book.asks),
+        }
+    }
+
+    pub fn convert_daily_volume(&self, volume: BinanceDailyVolume) -> BinanceDailyVolume {
+        BinanceDailyVolume {
+            last_price: self.to_usd(volume.last_price),
+            quote_volume: self.to_usd(volume.quote_volume),
+            ..volume
+        }
+    }
+}
+
+fn quote_usd_rate(quote: &str, tickers: &HashMap<&str, &BinanceDailyVolume>) -> Option<Decimal> {
+    if USD_QUOTES.contains(&quote) {
+        return Some(Decimal::ONE);
+    }
+
+    let symbol = format!("{}{}", quote, DEFAULT_QUOTE);
+    tickers
+        .get(symbol.as_str())
+        .map(|ticker| ticker.last_price)
+        .filter(|price| price.is_sign_positive() && !price.is_zero())
+}
+
+// Chooses the trading pair with the biggest 24h quote volume in USD for every base asset.
+// Pairs with quote assets which can't be converted to USD are skipped.
+pub fn resolve_spot_pairs(
+    symbols: &[BinanceSpotExchangeInfoSymbol],
+    tickers: &[BinanceDailyVolume],
+) -> HashMap<String, SpotPair> {
+    let tickers = tickers
+        .iter()
+        .map(|ticker| (ticker.symbol.as_str(), ticker))
+        .collect::<HashMap<_, _>>();
+
+    let mut best: HashMap<String, (Decimal, SpotPair)> = HashMap::new();
+
+    for item in symbols.iter().filter(|item| item.status == "TRADING") {
+        let Some(usd_rate) = quote_usd_rate(&item.quote_asset, &tickers) else {
+            continue;
+        };
+        let Some(ticker) = tickers.get(item.symbol.as_str()) else {
+            continue;
+        };
+
+        let usd_volume = ticker.quote_volume * usd_rate;
+        if best.get(&item.base_asset).is_some_and(|(volume, _)| *volume >= usd_volume) {
+            continue;
+        }
+
+        let pair = SpotPair {
+            symbol: item.symbol.clone(),
+            base_asset: item.base_asset.clone(),
+            quote_asset: item.quote_asset.clone(),
+            usd_rate,
+        };
+        best.insert(item.base_asset.clone(), (usd_volume, pair));
+    }
+
+    best.into_iter().map(|(asset, (_, pair))| (asset, pair)).collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolInfo {
+    pub symbol: String,
+    pub base_asset: String,
+    pub quote_asset: String,
+    pub tick_size: Option<Decimal>,
+    // Binance spot exchange info doesn't provide listing dates
+    pub onboard_date: Option<DateTime<Utc>>,
+}
+
+fn find_tick_size(filters: &[BinanceSymbolFilter]) -> Option<Decimal> {
+    filters
+        .iter()
+        .find(|filter| filter.filter_type == "PRICE_FILTER")
+        .and_then(|filter| filter.tick_size)
+        .map(|tick_size| tick_size.normalize())
+}
+
+// Trading perpetual contracts by symbol
+fn collect_futures_symbols(exch_info: BinanceExchangeInfoResponse) -> HashMap<String, SymbolInfo> {
+    exch_info
+        .symbols
+        .into_iter()
+        .filter(|item| &item.status == "TRADING" && &item.contract_type == "PERPETUAL")
+        .map(|item| {
+            let info = SymbolInfo {
+                tick_size: find_tick_size(&item.filters),
+                symbol: item.symbol.clone(),
+                base_asset: item.base_asset,
+                quote_asset: item.quote_asset,
+                onboard_date: Some(item.onboard_date),
+            };
+
+            (item.symbol, info)
+        })
+        .collect()
+}
+
+// Trading spot pairs by symbol
+fn collect_spot_symbols(symbols: &[BinanceSpotExchangeInfoSymbol]) -> HashMap<String, SymbolInfo> {
+    symbols
+        .iter()
+        .filter(|item| &item.status == "TRADING")
+        .map(|item| {
+            let info = SymbolInfo {
+                symbol: item.symbol.clone(),
+                base_asset: item.base_asset.clone(),
+                quote_asset: item.quote_asset.clone(),
+                tick_size: find_tick_size(&item.filters),
+                onboard_date: None,
+            };
+
+            (item.symbol.clone(), info)
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuturesContract {
+    pub info: SymbolInfo,
+    // Number of base asset units in one contract unit
+    pub multiplier: Decimal,
+}
+
+impl FuturesContract {
+    fn to_base_price(&self, price: Decimal) -> Decimal {
+        (price / self.multiplier).normalize()
+    }
+
+    pub fn to_base_amount(&self, amount: Decimal) -> Decimal {
+        amount * self.multiplier
+    }
+
+    // Prices and base volumes are converted to a single base asset unit,
+    // quote volumes don't depend on the multiplier
+    pub fn normalize_candles(&self, candles: Vec<BinanceCandle>) -> Vec<BinanceCandle> {
+        candles
+            .into_iter()
+            .map(|candle| BinanceCandle {
+                open: self.to_base_price(candle.open),
+                high: self.to_base_price(candle.high),
+                low: self.to_base_price(candle.low),
+                close: self.to_base_price(candle.close),
+                volume: self.to_base_amount(candle.volume),
+                buy_base_volume: self.to_base_amount(candle.buy_base_volume),
+                ..candle
+            })
+            .collect()
+    }
+
+    pub fn normalize_premium_index(&self, rate: BinanceFundingRate) -> BinanceFundingRate {
+        BinanceFundingRate {
+            mark_price: self.to_base_price(rate.mark_price),
+            index_price: self.to_base_price(rate.index_price),
+            ..rate
+        }
+    }
+}
+
+// Splits 1000PEPE into PEPE and 1000, assets without a known prefix get multiplier 1
+fn split_multiplier(base_asset: &str) -> (&str, Decimal) {
+    CONTRACT_MULTIPLIERS
+        .iter()
+        .find_map(|(prefix, multiplier)| {
+            base_asset
+                .strip_prefix(prefix)
+                .filter(|asset| !asset.is_empty())
+                .map(|asset| (asset, Decimal::from(*multiplier)))
+        })
+        .unwrap_or((base_asset, Decimal::ONE))
+}
+
+// USDT perpetual contracts by the asset they are traded for. A contract named
+// exactly after the asset wins over a multiplied one. A prefixed base asset traded
+// on spot is a real asset, e.g. 1000SATS, so it isn't treated as multiplied.
+fn collect_futures_contracts(
+    futures: &HashMap<String, SymbolInfo>,
+    spot_assets: &HashSet<String>,
+) -> HashMap<String, FuturesContract> {
+    let mut contracts: HashMap<String, FuturesContract> = HashMap::new();
+
+    for info in futures.values().filter(|info| info.quote_asset == DEFAULT_QUOTE) {
+        let exact = FuturesContract {
+            info: info.clone(),
+            multiplier: Decimal::ONE,
+        };
+        contracts.insert(info.base_asset.clone(), exact);
+    }
+
+    for info in futures.values().filter(|info| info.quote_asset == DEFAULT_QUOTE) {
+        let (asset, multiplier) = split_multiplier(&info.base_asset);
+        if multiplier == Decimal::ONE || contracts.contains_key(asset) || spot_assets.contains(&info.base_asset) {
+            continue;
+        }
+
+        let contract = FuturesContract {
+            info: info.clone(),
+            multiplier,
+        };
+        contracts.insert(asset.to_string(), contract);
+    }
+
+    contracts
+}
+
+#[derive(Debug, Default)]
+struct Symbols {
+    spot: HashMap<String, SymbolInfo>,
+    futures: HashMap<String, SymbolInfo>,
+    // The most liquid spot pair of every base asset
+    spot_pairs: HashMap<String, SpotPair>,
+    futures_contracts: HashMap<String, FuturesContract>,
+}
+
+impl Symbols {
+    fn is_loaded(&self) -> bool {
+        !self.spot.is_empty() && !self.spot_pairs.is_empty() && !self.futures.is_empty()
+    }
+
+    // Perpetual contract of the margin asset, the config overrides go first
+    fn futures_contract(&self, asset: &str, aliases: &HashMap<String, FuturesAlias>) -> Option<FuturesContract> {
+        if let Some(alias) = aliases.get(asset) {
+            let info = self.futures.get(&alias.symbol)?.clone();
+            return Some(FuturesContract { info, multiplier: alias.multiplier });
+        }
+
+        self.futures_contracts.get(asset).cloned()
+    }
+}
+
+// Tracked assets whose perpetual contract wasn't trading during the previous refresh.
+// Each contract is announced once, an asset named exactly after it goes first.
+fn find_new_contracts(
+    previous: &HashMap<String, SymbolInfo>,
+    current: &Symbols,
+    aliases: &HashMap<String, FuturesAlias>,
+    tracked: &[String],
+) -> Vec<(String, FuturesContract)> {
+    let mut candidates = tracked
+        .iter()
+        .filter_map(|asset| {
+            let contract = current.futures_contract(asset, aliases)?;
+            let is_new = !previous.contains_key(&contract.info.symbol);
+            is_new.then(|| (asset.clone(), contract))
+        })
+        .collect::<Vec<_>>();
+    candidates.sort_by(|(a, a_contract), (b, b_contract)| {
+        (*a != a_contract.info.base_asset, a).cmp(&(*b != b_contract.info.base_asset, b))
+    });
+
+    let mut seen = HashSet::new();
+    candidates.retain(|(_, contract)| seen.insert(contract.info.symbol.clone()));
+    candidates
+}
+
+pub struct SymbolRegistry {
+    binance: Binance,
+    symbols: RwLock<Symbols>,
+    // Margin asset to futures symbol overrides for renamed tickers
+    futures_aliases: HashMap<String, FuturesAlias>,
+}
+
+impl SymbolRegistry {
+    // Retries until spot and futures symbols are loaded, so the first reports don't miss any data.
+    // Gives up after a few attempts and starts with whatever was loaded.
+    pub async fn load(binance: Binance, futures_aliases: HashMap<String, FuturesAlias>) -> Self {
+        let registry = Self {
+            binance,
+            symbols: RwLock::new(Symbols::default()),
+            futures_aliases,
+        };
+
+        for attempt in 1..=WARM_UP_RETRIES {
+            registry.refresh(&[]).await;
+            if registry.symbols.read().await.is_loaded() {
+                return registry;
+            }
+
+            if attempt < WARM_UP_RETRIES {
+                error!("Failed to load exchange info, retrying");
+                tokio::time::sleep(WARM_UP_RETRY_INTERVAL).await;
+            }
+        }
+
+        error!("Failed to load exchange info, starting with partial symbols");
+        registry
+    }
+
+    // Spot symbols, spot pairs and futures symbols are updated independently,
+    // a part keeps its previous symbols if its request fails. Delisted symbols are dropped.
+    // Returns the tracked assets which got a new perpetual contract.
+    async fn refresh(&self, tracked: &[String]) -> Vec<(String, FuturesContract)> {
+        let spot_info = self.binance.get_spot_exchange_info().await;
+        let tickers = self.binance.get_spot_daily_volumes().await;
+        let futures_info = self.binance.get_futures_exchange_info().await;
+
+        let mut lock = self.symbols.write().await;
+
+        match &spot_info {
+            Ok(spot_info) => {
+                lock.spot = collect_spot_symbols(&spot_info.symbols);
+                info!("Loaded {} spot symbols", lock.spot.len());
+            }
+            Err(e) => error!("Failed to update exchange info Binance Spot: {}", e),
+        }
+
+        match (&spot_info, tickers) {
+            (Ok(spot_info), Ok(tickers)) => lock.spot_pairs = resolve_spot_pairs(&spot_info.symbols, &tickers),
+            (_, Err(e)) => error!("Failed to update daily volumes Binance Spot: {}", e),
+            _ => {}
+        }
+
+        let futures = match futures_info {
+            Ok(futures_info) => collect_futures_symbols(futures_info),
+            Err(e) => {
+                error!("Failed to update exchange info Binance Futures: {}", e);
+                return Vec::new();
+            }
+        };
+        info!("Loaded {} futures symbols", futures.len());
+
+        let spot_assets = lock.spot.values().map(|info| info.base_asset.clone()).collect();
+        let current = Symbols {
+            futures_contracts: collect_futures_contracts(&futures, &spot_assets),
+            futures,
+            ..Default::default()
+        };
+
+        // Nothing is new if futures symbols haven't been loaded before
+        let new_contracts = if lock.futures.is_empty() {
+            Vec::new()
+        } else {
+            find_new_contracts(&lock.futures, &current, &self.futures_aliases, tracked)
+        };
+
+        lock.futures = current.futures;
+        lock.futures_contracts = current.futures_contracts;
+
+        new_contracts
+    }
+
+    pub async fn futures_contract(&self, asset: &str) -> Option<FuturesContract> {
+        let lock = self.symbols.read().await;
+        lock.futures_contract(asset, &self.futures_aliases)
+    }
+
+    pub async fn spot_symbol(&self, symbol: &str) -> Option<SymbolInfo> {
+        self.symbols.read().await.spot.get(symbol).cloned()
+    }
+
+    pub async fn spot_pair(&self, asset: &str) -> SpotPair {
+        let lock = self.symbols.read().await;
+
+        lock.spot_pairs
+            .get(asset)
+            .cloned()
+            .unwrap_or_else(|| SpotPair::with_default_quote(asset))
+    }
+}
+
+// Contracts found on load are not announced, only the ones listed while the service is running
+pub async fn periodic_symbols_update(
+    registry: Arc<SymbolRegistry>,
+    margin_processor: Arc<MarginDataProcessor>,
+    report_tx: Sender<MarginDataMessage>,
+) {
+    // The registry is already filled on load
+    let start = Instant::now() + EXCHANGE_INFO_UPDATE_INTERVAL;
+    let mut interval = interval_at(start, EXCHANGE_INFO_UPDATE_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        // Only tracked margin assets are announced
+        let tracked = margin_processor.snapshot().await;
+        let assets = tracked.keys().cloned().collect::<Vec<_>>();
+
+        let new_contracts = registry.refresh(&assets).await;
+
+        for (asset, contract) in new_contracts {
+            let Some(data) = tracked.get(&asset).cloned() else {
+                continue;
+            };
+
+            info!("New perpetual contract {} for {}", contract.info.symbol, asset);
+            let listing = FuturesListing { contract, data };
+            report_tx.send(MarginDataMessage::FuturesListing(listing)).await.unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn symbol(base: &str, quote: &str, status: &str) -> BinanceSpotExchangeInfoSymbol {
+        BinanceSpotExchangeInfoSymbol {
+            symbol: format!("{}{}", base, quote),
+            status: status.to_string(),
+            base_asset: base.to_string(),
+            quote_asset: quote.to_string(),
+            filters: Vec::new(),
+        }
+    }
+
+    fn ticker(symbol: &str, last_price: i64, quote_volume: i64) -> BinanceDailyVolume {
+        BinanceDailyVolume {
+            symbol: symbol.to_string(),
+            last_price: Decimal::from(last_price),
+            volume: Decimal::ZERO,
+            quote_volume: Decimal::from(quote_volume),
+        }
+    }
+
+    #[test]
+    fn test_resolve_spot_pairs() {
+        let symbols = vec![
+            symbol("BTC", "USDT", "TRADING"),
+            symbol("AAA", "USDT", "BREAK"),
+            symbol("AAA", "USDC", "TRADING"),
+            symbol("AAA", "BTC", "TRADING"),
+            symbol("BBB", "USDT", "TRADING"),
+            symbol("BBB", "TRY", "TRADING"),
+            symbol("CCC", "TRY", "TRADING"),
+        ];
+
+        let tickers = vec![
+            ticker("BTCUSDT", 100_000, 1_000_000_000),
+            ticker("AAAUSDT", 1, 900_000),
+            ticker("AAAUSDC", 1, 500_000),
+            // 10 BTC is worth 1M USD
+            ticker("AAABTC", 0, 10),
+            ticker("BBBUSDT", 2, 1_000),
+            ticker("BBBTRY", 80, 1_000_000),
+            ticker("CCCTRY", 80, 1_000_000),
+        ];
+
+        let result = resolve_spot_pairs(&symbols, &tickers);
+
+        let expected = SpotPair {
+            symbol: "AAABTC".to_string(),
+            base_asset: "AAA".to_string(),
+            quote_asset: "BTC".to_string(),
+            usd_rate: Decimal::from(100_000),
+        };
+        assert_eq!(result["AAA"], expected);
+        assert_eq!(result["BBB"], SpotPair::with_default_quote("BBB"));
+        assert!(!result.contains_key("CCC"));
+        assert_eq!(result.len(), 3);
+    }
+
+    #[test]
+    fn test_collect_futures_symbols() {
+        let exch_info = r#"{"symbols": [
+            {"symbol": "AAAUSDT", "contractType": "PERPETUAL", "status": "TRADING", "baseAsset": "AAA",
+             "quoteAsset": "USDT", "onboardDate": 1569398400000,
+             "filters": [{"filterType": "PRICE_FILTER", "tickSize": "0.0100", "minPrice": "0.01"},
+                         {"filterType": "LOT_SIZE", "stepSize": "0.001"}]},
+            {"symbol": "BBBUSDT", "contractType": "PERPETUAL", "status": "SETTLING", "baseAsset": "BBB",
+             "quoteAsset": "USDT", "onboardDate": 1569398400000, "filters": []},
+            {"symbol": "AAAUSDT_250926", "contractType": "CURRENT_QUARTER", "status": "TRADING", "baseAsset": "AAA",
+             "quoteAsset": "USDT", "onboardDate": 1569398400000, "filters": []}
+        ]}"#;
+        let exch_info = serde_json::from_str::<BinanceExchangeInfoResponse>(exch_info).unwrap();
+
+        let result = collect_futures_symbols(exch_info);
+
+        let expected = SymbolInfo {
+            symbol: "AAAUSDT".to_string(),
+            base_asset: "AAA".to_string(),
+            quote_asset: "USDT".to_string(),
+            tick_size: Some(Decimal::new(1, 2)),
+            onboard_date: DateTime::from_timestamp_millis(1569398400000),
+        };
+        assert_eq!(result.len(), 1);
+        assert_eq!(result["AAAUSDT"], expected);
+    }
+
+    fn futures_info(base: &str) -> SymbolInfo {
+        SymbolInfo {
+            symbol: format!("{}USDT", base),
+            base_asset: base.to_string(),
+            quote_asset: "USDT".to_string(),
+            tick_size: None,
+            onboard_date: None,
+        }
+    }
+
+    #[test]
+    fn test_collect_futures_contracts() {
+        let futures = ["SOL", "1000PEPE", "1000000MOG", "1MBABYDOGE", "1INCH", "1000SATS"]
+            .into_iter()
+            .map(|base| (format!("{}USDT", base), futures_info(base)))
+            .collect::<HashMap<_, _>>();
+
+        // 1000SATS is traded on spot, so it's an asset of its own
+        let spot_assets = HashSet::from(["SOL".to_string(), "1000SATS".to_string()]);
+        let result = collect_futures_contracts(&futures, &spot_assets);
+
+        let multiplier = |asset: &str| result.get(asset).map(|contract| contract.multiplier);
+        assert_eq!(multiplier("SOL"), Some(Decimal::ONE));
+        assert_eq!(multiplier("PEPE"), Some(Decimal::ONE_THOUSAND));
+        assert_eq!(multiplier("MOG"), Some(Decimal::from(1_000_000)));
+        assert_eq!(multiplier("BABYDOGE"), Some(Decimal::from(1_000_000)));
+        assert_eq!(multiplier("1INCH"), Some(Decimal::ONE));
+        assert_eq!(multiplier("1000SATS"), Some(Decimal::ONE));
+        assert_eq!(multiplier("SATS"), None);
+        assert_eq!(result["PEPE"].info.symbol, "1000PEPEUSDT");
+    }
+
+    #[test]
+    fn test_futures_contract_alias() {
+        let futures = ["RENDER", "1000SATS", "1000NEW"]
+            .into_iter()
+            .map(|base| (format!("{}USDT", base), futures_info(base)))
+            .collect::<HashMap<_, _>>();
+        let symbols = Symbols {
+            futures,
+            ..Default::default()
+        };
+
+        let alias = |symbol: &str, multiplier: Decimal| FuturesAlias {
+            symbol: symbol.to_string(),
+            multiplier,
+        };
+        let aliases = HashMap::from([
+            ("RNDR".to_string(), alias("RENDERUSDT", Decimal::ONE)),
+            ("SATS".to_string(), alias("1000SATSUSDT", Decimal::ONE)),
+            ("OLD".to_string(), alias("1000NEWUSDT", Decimal::ONE_THOUSAND)),
+            ("GONE".to_string(), alias("GONEUSDT", Decimal::ONE)),
+        ]);
+
+        let multiplier = |asset: &str| symbols.futures_contract(asset, &aliases).map(|contract| contract.multiplier);
+        assert_eq!(multiplier("RNDR"), Some(Decimal::ONE));
+        assert_eq!(multiplier("SATS"), Some(Decimal::ONE));
+        assert_eq!(multiplier("OLD"), Some(Decimal::ONE_THOUSAND));
+        assert_eq!(multiplier("GONE"), None);
+    }
+
+    #[test]
+    fn test_futures_alias_config() {
+        let aliases: HashMap<String, FuturesAlias> = serde_json::from_str(
+            r#"{ "RNDR": "RENDERUSDT", "OLD": { "symbol": "1000NEWUSDT", "multiplier": 1000 } }"#,
+        )
+        .unwrap();
+
+        assert_eq!(aliases["RNDR"].symbol, "RENDERUSDT");
+        assert_eq!(aliases["RNDR"].multiplier, Decimal::ONE);
+        assert_eq!(aliases["OLD"].symbol, "1000NEWUSDT");
+        assert_eq!(aliases["OLD"].multiplier, Decimal::ONE_THOUSAND);
+    }
+
+    #[test]
+    fn test_find_new_contracts() {
+        let to_map = |bases: &[&str]| {
+            bases
+                .iter()
+                .map(|base| (format!("{}USDT", base), futures_info(base)))
+                .collect::<HashMap<_, _>>()
+        };
+
+        let previous = to_map(&["SOL", "RNDR"]);
+
+        let futures = to_map(&["SOL", "1000PEPE", "RENDER", "AAA"]);
+        let current = Symbols {
+            futures_contracts: collect_futures_contracts(&futures, &HashSet::new()),
+            futures,
+            ..Default::default()
+        };
+
+        let aliases = HashMap::from([(
+            "RNDR".to_string(),
+            FuturesAlias {
+                symbol: "RENDERUSDT".to_string(),
+                multiplier: Decimal::ONE,
+            },
+        )]);
+
+        let tracked = ["SOL", "PEPE", "1000PEPE", "RENDER", "RNDR", "AAA", "BBB"].map(String::from);
+        let mut result = find_new_contracts(&previous, &current, &aliases, &tracked)
+            .into_iter()
+            .map(|(asset, contract)| (asset, contract.info.symbol))
+            .collect::<Vec<_>>();
+        result.sort();
+
+        // every contract is announced once, by the asset named after it
+        let expected = vec![
+            ("1000PEPE".to_string(), "1000PEPEUSDT".to_string()),
+            ("AAA".to_string(), "AAAUSDT".to_string()),
+            ("RENDER".to_string(), "RENDERUSDT".to_string()),
+        ];
+        assert_eq!(result, expected);
+
+        // untracked assets are not resolved
+        let tracked = ["PEPE", "RNDR"].map(String::from);
+        let mut result = find_new_contracts(&previous, &current, &aliases, &tracked)
+            .into_iter()
+            .map(|(asset, contract)| (asset, contract.info.symbol))
+            .collect::<Vec<_>>();
+        result.sort();
+
+        let expected = vec![
+            ("PEPE".to_string(), "1000PEPEUSDT".to_string()),
+            ("RNDR".to_string(), "RENDERUSDT".to_string()),
+        ];
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_convert_order_book() {
+        let pair = SpotPair {
+            usd_rate: Decimal::TEN,
+            ..SpotPair::with_default_quote("AAA")
+        };
+
+        let book = BinanceOrderBook {
+            bids: vec![[Decimal::ONE, Decimal::TWO]],
+            asks: vec![[Decimal::TWO, Decimal::ONE]],
+        };
+
+        let result = pair.convert_order_book(book);
+        assert_eq!(result.bids, vec![[Decimal::TEN, Decimal::TWO]]);
+        assert_eq!(result.asks, vec![[Decimal::from(20), Decimal::ONE]]);
+    }
+}
@@ -1,27 +1,53 @@
 use log::error;
 use numfmt::Formatter;
-use reqwest::Client;
+use reqwest::{Client, RequestBuilder};
+use reqwest::multipart::{Form, Part};
 use rust_decimal::Decimal;
 use serde_json::{json, Value};
 use rust_decimal::prelude::ToPrimitive;
 
 use crate::binance::BinanceDailyVolume;
 use crate::config::TelegramConfig;
+use crate::digest::{Digest, DigestEntry, DigestPeriod};
+use crate::squeeze::SqueezeScore;
+use crate::leverage::LeverageSummary;
 use crate::report::{
+    BasisReport,
+    CurrentOpenInterest,
     FundingRateReport,
     FuturesReport,
+    InterestRateReport,
+    LiquidationVolume,
+    NewAssetReport,
     LongShortRatioReport,
     MarginDataReport,
+    MarketContext,
     OpenInterestChange,
+    OrderBookReport,
+    PriceReport,
     Report,
     SpotReport,
-    AggregatedVolume
+    AggregatedVolume,
+    VolumeRatio,
+};
+use crate::structs::{
+    FuturesListing,
+    InventoryDepletion,
+    InventoryDepletionKind,
+    IsolatedMarginData,
+    MarginData,
+    SignalKind,
+    TimeDifference,
 };
-use crate::structs::{MarginData, TimeDifference};
 
 const TELEGRAM_API: &str = "https://api.telegram.org";
 const MARKDOVWN2_ESCAPE_SYMBOLS: &str = r#"\\[]()~>#\+-={}.!""#;
 const MARKDOVWN2_SYMBOLS: &str = r#"*_"#;
+// Symbols which open and close entities, a cut line must not keep them unpaired
+const MARKDOWN_ENTITY_SYMBOLS: &str = "*_`";
+const CODE_FENCE: &str = "```";
+const CAPTION_MAX_LENGTH: usize = 1024;
+const MESSAGE_MAX_LENGTH: usize = 4096;
 
 fn format_number(f: &mut Formatter, num: Decimal) -> String {
     let num = num.trunc_with_scale(2).normalize();
@@ -57,19 +83,56 @@ fn dollar_formatter() -> Formatter {
 }
 
 fn format_spot_report(data: SpotReport) -> String {
-    let mut msg = "💸 *Spot*\n\n".to_string();
+    let pair = &data.pair;
+    let mut msg = format!("💸 *Spot* {}/{}", pair.base_asset, pair.quote_asset);
+
+    if !pair.is_usd_quoted() {
+        msg.push_str(" (in USD)");
+    }
+    msg.push_str("\n\n");
+
+    if let Some(price) = data.price {
+        msg.push_str(&format_price_report(price));
+        msg.push('\n');
+    }
 
-    let daily_vol = format_daily_volume_report(data.daily_volume);
+    let daily_vol = format_daily_volume_report(data.daily_volume, &data.pair.base_asset);
     msg.push_str(&daily_vol);
-    msg.push_str("\n");
+    msg.push('\n');
 
-    let volumes = format_spot_volume_report(data.volume_change);
+    let volumes = format_volume_report(data.volume_change);
     msg.push_str(&volumes);
 
+    if !data.relative_volume.is_empty() {
+        msg.push('\n');
+        msg.push_str(&relative_volume_report(data.relative_volume));
+    }
+
+    msg.push('\n');
+    msg.push_str(&order_book_report(data.order_book));
+
     msg
 }
 
-fn format_spot_volume_report(data: Vec<AggregatedVolume>) -> String {
+fn format_price_report(data: PriceReport) -> String {
+    let mut f = Formatter::default();
+    let mut msg = format!("💲 Price *{}*: ", data.current);
+
+    for item in data.changes {
+        let change = format!("• _{}_ *{}*% ", item.interval, format_change(&mut f, item.change));
+        msg.push_str(&change);
+    }
+
+    let range = format!(
+        "\n📏 4h range {} - {} (*{}*%) • VWAP *{}*",
+        data.low, data.high, format_number(&mut f, data.range), data.vwap
+    );
+    msg.push_str(&range);
+
+    msg
+}
+
+fn format_volume_report(data: Vec<AggregatedVolume>) -> String {
     if data.is_empty() {
         return "Trading volumes: no data".to_string()
     };
@@ -92,7 +155,7 @@ fn format_spot_volume_report(data: Vec<AggregatedVolume>) -> String {
     format!("{}\n{}\n{}", buy_msg, sell_msg, ratio_msg)
 }
 
-fn format_daily_volume_report(data: Option<BinanceDailyVolume>) -> String {
+fn format_daily_volume_report(data: Option<BinanceDailyVolume>, base_asset: &str) -> String {
     let mut msg = "💰 24h volume: ".to_string();
 
     let Some(report) = data else {
@@ -105,14 +168,14 @@ fn format_daily_volume_report(data: Option<BinanceDailyVolume>) -> String {
     let doll_vol = format_number(&mut f_doll, report.quote_volume);
     let vol = format_number(&mut f, report.volume);
 
-    let symbol = report.symbol.strip_suffix("USDT").unwrap_or(&report.symbol);
-    let vol_msg = format!("*{}* ({} {})", doll_vol, vol, symbol);
+    let vol_msg = format!("*{}* ({} {})", doll_vol, vol, base_asset);
 
     msg.push_str(&vol_msg);
     msg
 }
 
-fn format_futures_report(data: Option<FuturesReport>) -> String {
+// OI amount is converted to units of the margin asset, whatever the contract is named
+fn format_futures_report(asset: &str, data: Option<FuturesReport>) -> String {
     let mut msg = "💸 *Futures*".to_string();
 
     let Some(report) = data else {
@@ -120,18 +183,50 @@ fn format_futures_report(data: Option<FuturesReport>) -> String {
         return msg
     };
 
+    let contract = &report.contract;
+    msg.push_str(&format!(" {}", contract.info.symbol));
+
+    if contract.multiplier != Decimal::ONE {
+        msg.push_str(&format!(" (x{})", contract.multiplier));
+    }
+
+    if let Some(onboard_date) = contract.info.onboard_date {
+        msg.push_str(&format!(" • _listed_ {}", onboard_date.format("%Y-%m-%d")));
+    }
+
     let funding = funding_rate_report(report.funding_rate);
     msg.push_str("\n\n");
     msg.push_str(&funding);
 
-    let open_interest = open_interest_report(report.open_interest);
-    msg.push_str("\n");
+    if let Some(basis) = report.basis {
+        msg.push('\n');
+        msg.push_str(&basis_report(basis));
+    }
+
+    let open_interest = open_interest_report(report.open_interest, report.current_open_interest, asset);
+    msg.push('\n');
     msg.push_str(&open_interest);
 
-    let long_short_ratio = long_short_ratio_report(report.long_short_ratio);
-    msg.push_str("\n");
+    let liquidations = liquidations_report(report.liquidations);
+    msg.push('\n');
+    msg.push_str(&liquidations);
+
+    let long_short_ratio = long_short_ratio_report(
+        report.long_short_ratio,
+        report.top_account_ratio,
+        report.top_position_ratio,
+    );
+    msg.push('\n');
     msg.push_str(&long_short_ratio);
 
+    let volumes = format_volume_report(report.volume_change);
+    msg.push_str("\n\n");
+    msg.push_str(&volumes);
+
+    let futures_spot_ratio = futures_spot_ratio_report(report.futures_spot_ratio);
+    msg.push('\n');
+    msg.push_str(&futures_spot_ratio);
+
     msg
 }
 
@@ -140,15 +235,122 @@ fn funding_rate_report(data: Option<FundingRateReport>) -> String {
         return "Funding rate: no data".to_string()
     };
 
+    let mut msg = format!(
+        "⏳ Funding rate *{}* in *{}* (APR *{}%*)",
+        report.funding_rate, report.next_funding_time, report.annualized
+    );
+
+    if !report.history.is_empty() {
+        let history = report
+            .history
+            .iter()
+            .map(|rate| rate.to_string())
+            .collect::<Vec<_>>()
+            .join(" • ");
+
+        let history_str = format!("\n📜 Settled: {} • _24h_ *{}*", history, report.cumulative_24h);
+        msg.push_str(&history_str);
+    }
+
+    msg
+}
+
+fn basis_report(data: BasisReport) -> String {
+    let mut f = Formatter::default();
+
     format!(
-        "⏳ Funding rate *{}* in *{}*",
-        report.funding_rate, report.next_funding_time.to_string()
+        "📐 Basis *{}* (*{}* bps) • _mark_ {} • _index_ {}",
+        data.basis, format_change(&mut f, data.premium_bps), data.mark_price, data.index_price
     )
 }
 
-fn long_short_ratio_report(data: Vec<LongShortRatioReport>) -> String {
+// Shows the whole crowd and top traders side by side for every interval
+fn long_short_ratio_report(
+    global: Vec<LongShortRatioReport>,
+    top_accounts: Vec<LongShortRatioReport>,
+    top_positions: Vec<LongShortRatioReport>,
+) -> String {
     let mut msg = "⚖️ Long short ratios: ".to_string();
 
+    let intervals = [&global, &top_accounts, &top_positions]
+        .into_iter()
+        .max_by_key(|ratios| ratios.len())
+        .map(|ratios| ratios.iter().map(|ratio| ratio.interval).collect::<Vec<_>>())
+        .unwrap_or_default();
+
+    if intervals.is_empty() {
+        msg.push_str("no data");
+        return msg
+    };
+
+    msg.push_str("_all_ / _top accounts_ / _top positions_");
+
+    let mut f = Formatter::default();
+    let mut find_ratio = |ratios: &[LongShortRatioReport], interval| {
+        ratios
+            .iter()
+            .find(|ratio| ratio.interval == interval)
+            .map(|ratio| format_number(&mut f, ratio.ratio))
+            .unwrap_or_else(|| "-".to_string())
+    };
+
+    for interval in intervals {
+        let ratio_msg = format!(
+            "\n• _{}_ *{}* / *{}* / *{}*",
+            interval,
+            find_ratio(&global, interval),
+            find_ratio(&top_accounts, interval),
+            find_ratio(&top_positions, interval),
+        );
+        msg.push_str(&ratio_msg);
+    }
+
+    msg
+}
+
+fn order_book_report(data: Option<OrderBookReport>) -> String {
+    let mut msg = "📖 Order book: ".to_string();
+
+    let Some(report) = data else {
+        msg.push_str("no data");
+        return msg
+    };
+
+    let mut f = Formatter::default();
+    let mut f_doll = dollar_formatter();
+
+    let spread = format!("spread *{}* bps", format_number(&mut f, report.spread_bps));
+    msg.push_str(&spread);
+
+    for depth in report.depth {
+        let depth_msg = format!(
+            "\n• _±{}%_ 🟢 *{}* 🔴 *{}* imbalance *{}*",
+            depth.percent,
+            format_number(&mut f_doll, depth.bids),
+            format_number(&mut f_doll, depth.asks),
+            format_change(&mut f, depth.imbalance),
+        );
+        msg.push_str(&depth_msg);
+    }
+
+    msg
+}
+
+fn relative_volume_report(data: Vec<VolumeRatio>) -> String {
+    let mut msg = "📊 Volume vs average: ".to_string();
+
+    let mut f = Formatter::default();
+    for ratio in data {
+        let ratio_msg = format!("• _{}_ *{}x* ", ratio.interval, format_number(&mut f, ratio.ratio));
+        msg.push_str(&ratio_msg);
+    }
+
+    msg
+}
+
+fn futures_spot_ratio_report(data: Vec<VolumeRatio>) -> String {
+    let mut msg = "🔁 Futures/spot volume: ".to_string();
+
     if data.is_empty() {
         msg.push_str("no data");
         return msg
@@ -163,8 +365,38 @@ fn long_short_ratio_report(data: Vec<LongShortRatioReport>) -> String {
     msg
 }
 
-fn open_interest_report(data: Vec<OpenInterestChange>) -> String {
-    let mut msg = "💣 OI: ".to_string();
+// The stream sends only the latest liquidation of a symbol per second, so the volumes are a lower bound
+fn liquidations_report(data: Option<Vec<LiquidationVolume>>) -> String {
+    let mut msg = "🔥 Liquidated longs/shorts (sampled, at least): ".to_string();
+
+    let Some(data) = data.filter(|data| !data.is_empty()) else {
+        msg.push_str("no data");
+        return msg
+    };
+
+    let mut f_dol = dollar_formatter();
+    for item in data {
+        let long = format_number(&mut f_dol, item.long);
+        let short = format_number(&mut f_dol, item.short);
+        msg.push_str(&format!("• _{}_ *{}*/*{}* ", item.interval, long, short));
+    }
+
+    msg
+}
+
+fn open_interest_report(
+    data: Vec<OpenInterestChange>,
+    current: Option<CurrentOpenInterest>,
+    base_asset: &str,
+) -> String {
+    let mut msg = "💣 OI".to_string();
+
+    if let Some(current) = current {
+        let value = format_number(&mut dollar_formatter(), current.value);
+        let amount = format_number(&mut Formatter::default(), current.amount);
+        msg.push_str(&format!(" *{}* ({} {})", value, amount, base_asset));
+    }
+    msg.push_str(": ");
 
     if data.is_empty() {
         msg.push_str("no data");
@@ -180,11 +412,112 @@ fn open_interest_report(data: Vec<OpenInterestChange>) -> String {
     msg
 }
 
+fn format_squeeze_score(data: SqueezeScore) -> String {
+    let mut msg = format!("🧨 Squeeze score *{}*/100", data.score);
+
+    let factors = data
+        .main_factors
+        .into_iter()
+        .map(|item| format!("{} +{}", item.factor, item.points))
+        .collect::<Vec<_>>();
+
+    if !factors.is_empty() {
+        msg.push_str(&format!(" ({})", factors.join(", ")));
+    }
+
+    msg
+}
+
+fn format_market_context(data: &MarketContext) -> String {
+    let mut f = Formatter::default();
+
+    let lines = data
+        .moves
+        .iter()
+        .map(|item| {
+            let changes = item
+                .changes
+                .iter()
+                .map(|change| format!("_{}_ *{}*%", change.interval, format_change(&mut f, change.change)))
+                .collect::<Vec<_>>()
+                .join(" • ");
+
+            format!("🌐 {} {}", item.asset, changes)
+        })
+        .collect::<Vec<_>>();
+
+    let mut msg = lines.join("\n");
+
+    if data.moving {
+        msg.push_str("\n🌊 Market-wide move");
+    }
+
+    msg
+}
+
+fn format_signal_header(symbol: &str, kind: SignalKind) -> String {
+    match kind {
+        SignalKind::Borrow => format!("#*{}*", symbol),
+        SignalKind::RepaySurge => format!("#*{}* 🧯 Repay surge", symbol),
+        SignalKind::InterestRateJump => format!("#*{}* 💹 Interest rate jump", symbol),
+    }
+}
+
+// Hourly rates are tiny, so they are shown in percent with more precision
+fn format_interest_rate(rate: Decimal) -> String {
+    (rate * Decimal::ONE_HUNDRED).trunc_with_scale(6).normalize().to_string()
+}
+
+fn interest_rate_report(data: InterestRateReport) -> String {
+    let mut f = Formatter::default();
+    let mut msg = format!("💹 Hourly interest *{}%*", format_interest_rate(data.hourly_rate));
+
+    let mut changes = Vec::new();
+
+    if let Some(change) = data.change {
+        changes.push(format!("{}%", format_change(&mut f, change)));
+    }
+
+    if let Some(history_change) = data.history_change {
+        changes.push(format!("_{}h_ {}%", data.history_hours, format_change(&mut f, history_change)));
+    }
+
+    if !changes.is_empty() {
+        msg.push_str(&format!(" ({})", changes.join(" • ")));
+    }
+
+    msg
+}
+
+fn isolated_margin_report(pairs: &[IsolatedMarginData]) -> String {
+    let mut f = Formatter::default();
+    let mut f_dol = dollar_formatter();
+
+    let mut msg = "🔒 Isolated pairs borrowed:".to_string();
+
+    for pair in pairs {
+        let base_usdt = format_number(&mut f_dol, pair.base.total_borrow_in_usdt);
+        let base = format_number(&mut f, pair.base.total_borrow);
+        let quote_usdt = format_number(&mut f_dol, pair.quote.total_borrow_in_usdt);
+
+        msg.push_str(&format!(
+            "\n• {}/{} *{}* ({} {}) • _{}_ {}",
+            pair.base_asset, pair.quote_asset, base_usdt, base, pair.base_asset, pair.quote_asset, quote_usdt
+        ));
+    }
+
+    msg
+}
+
 fn format_margin_report_message(symbol: &str, data: MarginDataReport) -> String {
     let mut f = Formatter::default();
     let mut f_dol = dollar_formatter();
 
-    let mut msg = format!("#*{}*", symbol);
+    let mut msg = String::new();
+
+    if data.isolated_only {
+        msg.push_str("\n\n🔒 Isolated margin only");
+    }
 
     let total_borrow_usdt = format_number(&mut f_dol, data.total_borrow_usdt);
     let total_borrow = format_number(&mut f, data.total_borrow);
@@ -197,6 +530,10 @@ fn format_margin_report_message(symbol: &str, data: MarginDataReport) -> String
     );
     msg.push_str(&borrow_str);
 
+    if let Some(z_score) = data.borrow_z_score {
+        msg.push_str(&format!("\n📊 Borrow change is *{:.1}σ*", z_score));
+    }
+
     let total_repay_usdt = format_number(&mut f_dol, data.total_repay_usdt);
     let total_repay = format_number(&mut f, data.total_repay);
     let repay_change = format_change(&mut f, data.repay_change);
@@ -211,23 +548,207 @@ fn format_margin_report_message(symbol: &str, data: MarginDataReport) -> String
     let ratio_str = format!("\n\n⚖️ B/R ratio *{}*", format_number(&mut f, data.br_ratio));
     msg.push_str(&ratio_str);
 
+    // Inventory of isolated pairs is unknown
+    if !data.isolated_only {
+        let available = format_number(&mut f, data.available);
+        let available_string = format!("\n🏦 Available *{}* {}", available, symbol);
+        msg.push_str(&available_string);
+    }
+
+    if let Some(interest_rate) = data.interest_rate {
+        msg.push('\n');
+        msg.push_str(&interest_rate_report(interest_rate));
+    }
+
+    if !data.isolated.is_empty() {
+        msg.push_str("\n\n");
+        msg.push_str(&isolated_margin_report(&data.isolated));
+    }
+
+    msg
+}
+
+pub fn format_inventory_depletion_message(data: InventoryDepletion) -> String {
+    let mut f = Formatter::default();
+    let mut f_dol = dollar_formatter();
+    let symbol = &data.data.asset;
+
+    let mut msg = format!("#*{}* 🪫 Inventory depletion", symbol);
+
+    let available = format_number(&mut f, data.data.available);
+    let change = match data.kind {
+        InventoryDepletionKind::SharpDrop => {
+            let change = format_change(&mut f, data.change_from_previous());
+            format!("{}% since last update", change)
+        }
+        InventoryDepletionKind::BelowRecentMax => {
+            let change = format_change(&mut f, data.change_from_recent_max());
+            let recent_max = format_number(&mut f, data.recent_max);
+            format!("{}% from recent max {}", change, recent_max)
+        }
+    };
+
+    let available_str = format!("\n\n🏦 Available *{}* {} ({})", available, symbol, change);
+    msg.push_str(&available_str);
+
+    let total_borrow_usdt = format_number(&mut f_dol, data.data.total_borrow_in_usdt);
+    let total_borrow = format_number(&mut f, data.data.total_borrow);
+    let borrow_str = format!("\n💳 Borrowed *{}* ({} {})", total_borrow_usdt, total_borrow, symbol);
+    msg.push_str(&borrow_str);
+
+    msg.push_str("\n\n#inventory");
+    msg
+}
+
+// Current borrow stats of the asset without changes
+fn format_margin_stats(data: &MarginData) -> String {
+    let mut f = Formatter::default();
+    let mut f_dol = dollar_formatter();
+    let symbol = &data.asset;
+
+    let total_borrow_usdt = format_number(&mut f_dol, data.total_borrow_in_usdt);
+    let total_borrow = format_number(&mut f, data.total_borrow);
+    let mut msg = format!("💳 Borrowed *{}* ({} {})", total_borrow_usdt, total_borrow, symbol);
+
+    let total_repay_usdt = format_number(&mut f_dol, data.total_repay_in_usdt);
+    let total_repay = format_number(&mut f, data.total_repay);
+    let repay_str = format!("\n💸 Repayed *{}* ({} {})", total_repay_usdt, total_repay, symbol);
+    msg.push_str(&repay_str);
+
+    if let Some(br_ratio) = data.total_borrow.checked_div(data.total_repay) {
+        msg.push_str(&format!("\n⚖️ B/R ratio *{}*", format_number(&mut f, br_ratio)));
+    }
+
     let available = format_number(&mut f, data.available);
-    let available_string = format!("\n🏦 Available *{}* {}", available, symbol);
-    msg.push_str(&available_string);
+    msg.push_str(&format!("\n🏦 Available *{}* {}", available, symbol));
+
+    if let Some(rate) = data.hourly_interest_rate {
+        msg.push_str(&format!("\n💹 Hourly interest *{}%*", format_interest_rate(rate)));
+    }
 
     msg
 }
 
-pub fn format_new_margin_data_message(data: MarginData) -> String {
-    format!("#*{}* 🆕\n\n#new", data.asset)
+pub fn format_futures_listing_message(listing: FuturesListing) -> String {
+    let contract = listing.contract;
+
+    let mut msg = format!("#*{}* 🚀 New perpetual *{}*", listing.data.asset, contract.info.symbol);
+    if contract.multiplier != Decimal::ONE {
+        msg.push_str(&format!(" (x{})", contract.multiplier));
+    }
+
+    msg.push_str("\n\n");
+    msg.push_str(&format_margin_stats(&listing.data));
+    msg.push_str("\n\n#listing");
+    msg
+}
+
+pub fn format_new_asset_report(report: NewAssetReport) -> String {
+    let margin = format_margin_stats(&report.data);
+    let spot = format_spot_report(report.spot);
+    let futures = format_futures_report(&report.data.asset, report.futures);
+
+    format!(
+        "#*{}* 🆕 New margin asset\n\n{}\n\n{}\n\n{}\n\n#new",
+        report.data.asset, margin, spot, futures
+    )
+}
+
+fn format_digest_table(title: &str, entries: Vec<DigestEntry>, suffix: &str) -> String {
+    let mut table = format!("{}\n", title);
+
+    if entries.is_empty() {
+        table.push_str("  no data\n");
+        return table
+    }
+
+    let mut f = Formatter::default();
+    for (position, entry) in entries.into_iter().enumerate() {
+        let value = format!("{}{}", format_number(&mut f, entry.value), suffix);
+        let row = format!("{:>2}. {:<10}{:>12}\n", position + 1, entry.asset, value);
+        table.push_str(&row);
+    }
+
+    table
+}
+
+pub fn format_digest_message(digest: Digest) -> String {
+    let title = match digest.period {
+        DigestPeriod::Hourly => "🕐 *Hourly digest*",
+        DigestPeriod::Daily => "📅 *Daily digest*",
+    };
+
+    let growth = format_digest_table("Borrow growth", digest.borrow_growth, "%");
+    let ratio = format_digest_table("B/R ratio", digest.br_ratio, "");
+    let available = format_digest_table("Borrowed of available", digest.borrow_to_available, "%");
+
+    format!("{}\n\n```\n{}\n{}\n{}```\n#digest", title, growth, ratio, available)
+}
+
+pub fn format_leverage_summary(summary: LeverageSummary) -> String {
+    let mut f = Formatter::default();
+    let mut f_dol = dollar_formatter();
+    let latest = summary.latest;
+
+    let change = |f: &mut Formatter, change: Option<Decimal>| {
+        change
+            .map(|change| format!(" {}% _24h_", format_change(f, change)))
+            .unwrap_or_default()
+    };
+
+    let mut msg = "📊 *Leverage index*\n\n".to_string();
+
+    msg.push_str(&format!(
+        "💳 Total borrowed *{}*{}",
+        format_number(&mut f_dol, latest.total_borrow_usdt), change(&mut f, summary.total_change)
+    ));
+
+    msg.push_str(&format!(
+        "\n💵 Stablecoins borrowed *{}*{}",
+        format_number(&mut f_dol, latest.stablecoin_borrow_usdt), change(&mut f, summary.stablecoin_change)
+    ));
+
+    if !latest.top.is_empty() {
+        msg.push_str("\n\nTop borrowed:");
+
+        for (position, item) in latest.top.into_iter().enumerate() {
+            msg.push_str(&format!(
+                "\n{}. {} *{}* ({}%)",
+                position + 1, item.asset, format_number(&mut f_dol, item.borrow_usdt), format_number(&mut f, item.share)
+            ));
+        }
+    }
+
+    msg.push_str("\n\n#leverage");
+    msg
+}
+
+pub fn format_removed_margin_data_message(data: MarginData) -> String {
+    let mut f = Formatter::default();
+    let mut f_dol = dollar_formatter();
+
+    let total_borrow_usdt = format_number(&mut f_dol, data.total_borrow_in_usdt);
+    let total_borrow = format_number(&mut f, data.total_borrow);
+
+    format!(
+        "#*{}* 🚫 Removed from margin\n\nLast borrowed *{}* ({} {})\n\n#delisted",
+        data.asset, total_borrow_usdt, total_borrow, data.asset
+    )
 }
 
 pub fn format_full_report(report: Report, updated: TimeDifference) -> String {
+    let market = format_market_context(&report.market);
+    let header = format_signal_header(&report.symbol, report.kind);
+    let header = if market.is_empty() { header } else { format!("{}\n\n{}", market, header) };
+    let squeeze = format_squeeze_score(report.squeeze);
     let margin = format_margin_report_message(&report.symbol, report.margin_data);
     let spot = format_spot_report(report.spot);
-    let futures = format_futures_report(report.futures);
+    let futures = format_futures_report(&report.symbol, report.futures);
 
-    let mut msg = format!("{}\n\n{}\n\n{}\n\nLast signal: ", margin, spot, futures);
+    let mut msg = format!(
+        "{}\n{}{}\n\n{}\n\n{}\n\nLast signal: ",
+        header, squeeze, margin, spot, futures
+    );
 
     if updated.is_none() {
         msg.push_str("never");
@@ -235,9 +756,109 @@ pub fn format_full_report(report: Report, updated: TimeDifference) -> String {
         msg.push_str(&format!("{}ago", updated));
     }
 
+    match report.kind {
+        SignalKind::RepaySurge => msg.push_str("\n\n#repay"),
+        SignalKind::InterestRateJump => msg.push_str("\n\n#rate"),
+        SignalKind::Borrow => {}
+    }
+
+    if report.market.moving {
+        msg.push_str("\n\n#market");
+    }
+
     msg
 }
 
+// Telegram counts length in UTF-16 code units after markdown parsing
+fn visible_length(text: &str) -> usize {
+    text
+        .chars()
+        .filter(|char| !MARKDOVWN2_SYMBOLS.contains(*char))
+        .map(char::len_utf16)
+        .sum()
+}
+
+// Takes the leading paragraphs which fit into the caption, the rest is returned separately.
+// The caption is empty if even the first paragraph is too long.
+fn split_caption(text: &str) -> (Option<String>, Option<String>) {
+    let paragraphs = text.split("\n\n").collect::<Vec<_>>();
+    let mut taken = 0;
+
+    while taken < paragraphs.len() && visible_length(&paragraphs[..=taken].join("\n\n")) <= CAPTION_MAX_LENGTH {
+        taken += 1;
+    }
+
+    let caption = (taken > 0).then(|| paragraphs[..taken].join("\n\n"));
+    let rest = (taken < paragraphs.len()).then(|| paragraphs[taken..].join("\n\n"));
+
+    (caption, rest)
+}
+
+// Markup is dropped from a line which has to be cut, so no entity is left unclosed
+fn truncate_line(line: &str, max_length: usize) -> String {
+    let mut length = 0;
+
+    line.chars()
+        .filter(|char| !MARKDOWN_ENTITY_SYMBOLS.contains(*char))
+        .take_while(|char| {
+            length += char.len_utf16();
+            length <= max_length
+        })
+        .collect()
+}
+
+// Splits the text by lines into messages within the limit, so markdown within a line stays intact.
+// A line which is longer than the limit is cut. A code block split between messages
+// is closed at the end of one and reopened at the start of the next.
+fn split_message(text: &str, max_length: usize) -> Vec<String> {
+    let mut messages = Vec::new();
+    let mut current = String::new();
+    let mut in_code = false;
+
+    for line in text.split('\n') {
+        let toggles_code = line.matches(CODE_FENCE).count() % 2 == 1;
+
+        // Room for the closing fence and the line break before it, unless the line closes the block
+        let reserved = if in_code && !toggles_code { CODE_FENCE.len() + 1 } else { 0 };
+        let limit = max_length - reserved;
+
+        let line = if visible_length(line) > limit - reserved {
+            truncate_line(line, limit - reserved)
+        } else {
+            line.to_string()
+        };
+
+        if !current.is_empty() && visible_length(&current) + 1 + visible_length(&line) > limit {
+            if in_code {
+                current.push('\n');
+                current.push_str(CODE_FENCE);
+            }
+
+            messages.push(std::mem::take(&mut current));
+
+            if in_code {
+                current.push_str(CODE_FENCE);
+            }
+        }
+
+        if !current.is_empty() {
+            current.push('\n');
+        }
+        current.push_str(&line);
+
+        // A cut line has no fences left
+        if line.matches(CODE_FENCE).count() % 2 == 1 {
+            in_code = !in_code;
+        }
+    }
+
+    if !current.trim().is_empty() {
+        messages.push(current);
+    }
+
+    messages
+}
+
 pub struct Telegram {
     token: String,
     chat: String,
@@ -259,6 +880,10 @@ impl Telegram {
         format!("{}/bot{}/sendMessage", TELEGRAM_API, self.token)
     }
 
+    fn photo_url(&self) -> String {
+        format!("{}/bot{}/sendPhoto", TELEGRAM_API, self.token)
+    }
+
     fn escape_markdown_v2(&self, text: &str) -> String {
         text.chars().fold(String::with_capacity(text.len()), |mut acc, char| {
             if MARKDOVWN2_ESCAPE_SYMBOLS.contains(char) && !MARKDOVWN2_SYMBOLS.contains(char) {
@@ -269,14 +894,40 @@ impl Telegram {
         })
     }
 
-    fn message(&self, text: &str) -> Value {
+    fn message(&self, text: &str, reply_to: Option<i64>) -> Value {
         let text = self.escape_markdown_v2(text);
 
-        json!({
+        let mut msg = json!({
             "chat_id": self.chat,
             "text": text,
             "parse_mode": "MarkdownV2"
-        })
+        });
+
+        if let Some(message_id) = reply_to {
+            msg["reply_parameters"] = json!({ "message_id": message_id });
+        }
+
+        msg
+    }
+
+    fn photo_message(&self, photo: Vec<u8>, caption: Option<&str>) -> Result<Form, reqwest::Error> {
+        let photo = Part::bytes(photo)
+            .file_name("chart.png")
+            .mime_str("image/png")?;
+
+        let form = Form::new()
+            .text("chat_id", self.chat.clone())
+            .part("photo", photo);
+
+        let Some(caption) = caption else {
+            return Ok(form)
+        };
+
+        let form = form
+            .text("caption", self.escape_markdown_v2(caption))
+            .text("parse_mode", "MarkdownV2");
+
+        Ok(form)
     }
 
     fn error_message(&self, text: String) -> Value {
@@ -286,25 +937,158 @@ impl Telegram {
         })
     }
 
+    // Returns the id of the sent message. Telegram errors like markdown parsing
+    // failures come with 400 status and are logged together with the response body.
+    async fn send(&self, request: RequestBuilder) -> Result<i64, String> {
+        let response = request.send().await.map_err(|e| e.to_string())?;
+        let status = response.status();
+        let body = response.text().await.map_err(|e| e.to_string())?;
+
+        if !status.is_success() {
+            return Err(format!("status {}, response {}", status, body));
+        }
+
+        let body = serde_json::from_str::<Value>(&body).map_err(|e| e.to_string())?;
+        body["result"]["message_id"].as_i64().ok_or_else(|| "No message id in response".to_string())
+    }
+
     pub async fn send_error_message(&self, err: String) {
-        let url = self.url();
         let msg = self.error_message(err);
+        let request = self.client.post(self.url()).json(&msg);
+
+        if let Err(e) = self.send(request).await {
+            error!("Failed to send message to telegram: {}, message text: {}", e, msg);
+        }
+    }
 
-        let result = self.client.post(url).json(&msg).send().await;
+    async fn send_text(&self, text: &str, reply_to: Option<i64>) {
+        for part in split_message(text, MESSAGE_MAX_LENGTH) {
+            let msg = self.message(&part, reply_to);
+            let request = self.client.post(self.url()).json(&msg);
 
-        if let Err(e) = result {
-            error!("Failed to send message to telegram: {:?}, message text: {}", e, msg);
+            if let Err(e) = self.send(request).await {
+                error!("Failed to send message to telegram: {}, message text: {}", e, msg);
+            }
         }
     }
 
     pub async fn send_message(&self, event: &str) {
-        let url = self.url();
-        let msg = self.message(event);
+        self.send_text(event, None).await;
+    }
 
-        let result = self.client.post(url).json(&msg).send().await;
+    // Sends the chart with the leading paragraphs of the text as its caption. The rest
+    // of the text is sent as a reply to the chart. The whole text is sent as a message
+    // if the chart can't be sent.
+    pub async fn send_photo(&self, text: &str, photo: Vec<u8>) {
+        let (caption, rest) = split_caption(text);
+
+        let result = match self.photo_message(photo, caption.as_deref()) {
+            Ok(form) => self.send(self.client.post(self.photo_url()).multipart(form)).await,
+            Err(e) => Err(e.to_string()),
+        };
+
+        match result {
+            Ok(message_id) => {
+                if let Some(rest) = rest {
+                    self.send_text(&rest, Some(message_id)).await;
+                }
+            }
+            Err(e) => {
+                error!("Failed to send photo to telegram: {}, caption: {:?}", e, caption);
+                self.send_message(text).await;
+            }
+        }
+    }
+}
 
-        if let Err(e) = result {
-            error!("Failed to send message to telegram: {:?}, message text: {}", e, msg);
+#[cfg(test)]
+mod test {
+    use crate::symbols::{FuturesContract, SymbolInfo};
+
+    use super::*;
+
+    fn futures_report(base_asset: &str, multiplier: Decimal) -> FuturesReport {
+        let info = SymbolInfo {
+            symbol: format!("{}USDT", base_asset),
+            base_asset: base_asset.to_string(),
+            quote_asset: "USDT".to_string(),
+            tick_size: None,
+            onboard_date: None,
+        };
+
+        FuturesReport {
+            contract: FuturesContract { info, multiplier },
+            funding_rate: None,
+            basis: None,
+            volume_change: Vec::new(),
+            futures_spot_ratio: Vec::new(),
+            long_short_ratio: Vec::new(),
+            top_position_ratio: Vec::new(),
+            top_account_ratio: Vec::new(),
+            current_open_interest: Some(CurrentOpenInterest {
+                value: Decimal::from(1_000_000),
+                amount: Decimal::from(5_000),
+            }),
+            open_interest: Vec::new(),
+            liquidations: None,
         }
     }
+
+    #[test]
+    fn test_format_futures_report_open_interest_unit() {
+        // 1000SATS is an asset of its own, the amount is in 1000SATS
+        let result = format_futures_report("1000SATS", Some(futures_report("1000SATS", Decimal::ONE)));
+        assert!(result.contains("(5.0 K 1000SATS)"), "{}", result);
+
+        // multiplied contract, the amount is converted to PEPE
+        let result = format_futures_report("PEPE", Some(futures_report("1000PEPE", Decimal::ONE_THOUSAND)));
+        assert!(result.contains("(5.0 K PEPE)"), "{}", result);
+
+        // renamed asset is labeled with the margin asset, not the contract
+        let result = format_futures_report("RNDR", Some(futures_report("RENDER", Decimal::ONE)));
+        assert!(result.contains("(5.0 K RNDR)"), "{}", result);
+        assert!(result.contains("RENDERUSDT"), "{}", result);
+    }
+
+    #[test]
+    fn test_split_caption() {
+        let text = "#*PEPE*\nscore\n\nmargin\n\nspot";
+        assert_eq!(split_caption(text), (Some(text.to_string()), None));
+
+        let long = "x".repeat(CAPTION_MAX_LENGTH);
+        let text = format!("#*PEPE*\n\n{}\n\nspot", long);
+        let (caption, rest) = split_caption(&text);
+        assert_eq!(caption, Some("#*PEPE*".to_string()));
+        assert_eq!(rest, Some(format!("{}\n\nspot", long)));
+
+        let text = format!("{}y\n\nspot", long);
+        assert_eq!(split_caption(&text), (None, Some(text.clone())));
+    }
+
+    #[test]
+    fn test_split_message() {
+        let line = "*a*".repeat(100);
+        let text = vec![line.clone(); 50].join("\n");
+
+        let result = split_message(&text, MESSAGE_MAX_LENGTH);
+        assert_eq!(result.len(), 2);
+        assert!(result.iter().all(|item| visible_length(item) <= MESSAGE_MAX_LENGTH));
+        assert_eq!(result.join("\n"), text);
+
+        let long_line = "a".repeat(MESSAGE_MAX_LENGTH + 10);
+        let result = split_message(&long_line, MESSAGE_MAX_LENGTH);
+        assert_eq!(result, vec!["a".repeat(MESSAGE_MAX_LENGTH)]);
+
+        // markup of a cut line is dropped instead of being left unclosed
+        let result = split_message("*bold* `code` _x_ long", 12);
+        assert_eq!(result, vec!["bold code x ".to_string()]);
+
+        // code block is closed and reopened between messages
+        let text = ["```", "row 1", "row 2", "row 3", "```"].join("\n");
+        let result = split_message(&text, 14);
+        assert_eq!(result, vec!["```\nrow 1\n```", "```\nrow 2\n```", "```\nrow 3\n```"]);
+        assert!(result.iter().all(|item| item.matches(CODE_FENCE).count() == 2));
+
+        assert_eq!(split_message("short", MESSAGE_MAX_LENGTH), vec!["short".to_string()]);
+    }
 }
@@ -1,10 +1,12 @@
-use chrono::{DateTime, Duration, SubsecRound, Timelike, Utc};
+use chrono::{DateTime, Duration, DurationRound, NaiveTime, SubsecRound, Timelike, Utc};
 use numfmt::Numeric;
 use rust_decimal::Decimal;
 
+use crate::digest::DigestPeriod;
+
 #[cfg(test)]
 use crate::binance::{
-    BinanceCandleMarketTradeVolume,
+    BinanceCandle,
     BinanceLongShortRatioPositions,
     BinanceOpenInterest,
 };
@@ -55,14 +57,40 @@ fn get_time_slot(date: DateTime<Utc>) -> DateTime<Utc> {
     date + Duration::seconds(delay.into())
 }
 
+// Returns the closest digest time slot. When hourly and daily digests
+// fall on the same time, only the daily one is sent.
+pub fn next_digest_time(
+    now: DateTime<Utc>,
+    daily_at: NaiveTime,
+    hourly: bool,
+) -> (DateTime<Utc>, DigestPeriod) {
+    let today = now.date_naive().and_time(daily_at).and_utc();
+    let next_daily = if today > now { today } else { today + Duration::days(1) };
+
+    if !hourly {
+        return (next_daily, DigestPeriod::Daily);
+    }
+
+    let next_hourly = now
+        .duration_trunc(Duration::hours(1))
+        .expect("Failed to truncate datetime to hours")
+        + Duration::hours(1);
+
+    if next_hourly < next_daily {
+        (next_hourly, DigestPeriod::Hourly)
+    } else {
+        (next_daily, DigestPeriod::Daily)
+    }
+}
+
 #[cfg(test)]
-pub fn candles_fixture() -> Vec<BinanceCandleMarketTradeVolume> {
+pub fn candles_fixture() -> Vec<BinanceCandle> {
     let file = fs::read("./test_fixtures/candles.json").unwrap();
     let result = serde_json::from_slice::<Vec<Vec<Decimal>>>(&file).unwrap();
 
     result
         .into_iter()
-        .map(BinanceCandleMarketTradeVolume::from)
+        .map(BinanceCandle::from)
         .collect()
 }
 
@@ -119,4 +147,34 @@ mod test {
         let expected = "2025-10-17 00:01:00 UTC";
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn test_next_digest_time() {
+        let daily_at = NaiveTime::from_hms_opt(9, 0, 0).unwrap();
+
+        let date = "2025-10-16T08:12:06Z".parse::<DateTime<Utc>>().unwrap();
+        let (next, period) = next_digest_time(date, daily_at, false);
+        assert_eq!(next.to_string(), "2025-10-16 09:00:00 UTC");
+        assert_eq!(period, DigestPeriod::Daily);
+
+        let date = "2025-10-16T09:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let (next, period) = next_digest_time(date, daily_at, false);
+        assert_eq!(next.to_string(), "2025-10-17 09:00:00 UTC");
+        assert_eq!(period, DigestPeriod::Daily);
+
+        let date = "2025-10-16T08:12:06Z".parse::<DateTime<Utc>>().unwrap();
+        let (next, period) = next_digest_time(date, daily_at, true);
+        assert_eq!(next.to_string(), "2025-10-16 09:00:00 UTC");
+        assert_eq!(period, DigestPeriod::Daily);
+
+        let date = "2025-10-16T09:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let (next, period) = next_digest_time(date, daily_at, true);
+        assert_eq!(next.to_string(), "2025-10-16 10:00:00 UTC");
+        assert_eq!(period, DigestPeriod::Hourly);
+
+        let date = "2025-10-16T23:59:59Z".parse::<DateTime<Utc>>().unwrap();
+        let (next, period) = next_digest_time(date, daily_at, true);
+        assert_eq!(next.to_string(), "2025-10-17 00:00:00 UTC");
+        assert_eq!(period, DigestPeriod::Hourly);
+    }
 }